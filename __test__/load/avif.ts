@@ -0,0 +1,13 @@
+import {ImageLoader} from "../../index.js"
+import {readFileSync, writeFileSync} from "node:fs";
+
+const buffer = readFileSync("../_source/tree.png")
+const img = ImageLoader.fromPng(buffer)
+
+writeFileSync("../_out/png.avif", img.toAvif())
+writeFileSync("../_out/png_q40.avif", await img.toAvifAsync({quality: 40, speed: 10}))
+
+// decode, and re-encode the decoded image
+const avif = await ImageLoader.fromAvifAsync(readFileSync("../_source/tree.avif"))
+writeFileSync("../_out/avif.png", avif.toPng())
+writeFileSync("../_out/avif.avif", ImageLoader.fromAvif(img.toAvif()).toAvif())
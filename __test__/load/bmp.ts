@@ -2,7 +2,7 @@ import {ImageLoader} from "../../index.js"
 import {readFileSync, writeFileSync} from "node:fs";
 
 const buffer = readFileSync("../_source/tree.bmp")
-const img = ImageLoader.fromBmp([...buffer])
+const img = ImageLoader.fromBmp(buffer)
 
 const png = img.toPng()
-writeFileSync("../_out/bmp.png", Buffer.from(png))
\ No newline at end of file
+writeFileSync("../_out/bmp.png", png)
\ No newline at end of file
@@ -2,7 +2,7 @@ import {ImageLoader} from "../../index.js"
 import {readFileSync, writeFileSync} from "node:fs";
 
 const buffer = readFileSync("../_source/tree.exr")
-const img = ImageLoader.fromOpenEXR([...buffer])
+const img = ImageLoader.fromOpenEXR(buffer)
 
 const png = img.toPng()
-writeFileSync("../_out/exr.png", Buffer.from(png))
\ No newline at end of file
+writeFileSync("../_out/exr.png", png)
\ No newline at end of file
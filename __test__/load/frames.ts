@@ -0,0 +1,10 @@
+import {ImageLoader} from "../../index.js"
+import {readFileSync, writeFileSync} from "node:fs";
+
+const buffer = readFileSync("../_source/tree.gif")
+const anim = await ImageLoader.framesFromGifAsync(buffer)
+
+for (let i = 0; i < anim.frameCount(); i++) {
+    const {delay, disposal} = anim.frameInfo(i)
+    writeFileSync(`../_out/frame_${i}_${delay}ms_${disposal}.png`, anim.frame(i).toPng())
+}
@@ -2,7 +2,7 @@ import {ImageLoader} from "../../index.js"
 import {readFileSync, writeFileSync} from "node:fs";
 
 const buffer = readFileSync("../_source/tree.gif")
-const img = ImageLoader.fromGif([...buffer])
+const img = ImageLoader.fromGif(buffer)
 
 const png = img.toPng()
-writeFileSync("../_out/gif.png", Buffer.from(png))
\ No newline at end of file
+writeFileSync("../_out/gif.png", png)
\ No newline at end of file
@@ -2,7 +2,11 @@ import {ImageLoader} from "../../index.js"
 import {readFileSync, writeFileSync} from "node:fs";
 
 const buffer = readFileSync("../_source/tree.hdr")
-const img = ImageLoader.fromHdr([...buffer])
+const img = ImageLoader.fromHdr(buffer)
 
 const png = img.toPng()
-writeFileSync("../_out/hdr.png", Buffer.from(png))
\ No newline at end of file
+writeFileSync("../_out/hdr.png", png)
+
+// an environment map cropped and resized, written back as hdr
+const map = ImageLoader.fromOpenEXR(readFileSync("../_source/tree.exr"))
+writeFileSync("../_out/exr.hdr", await map.crop(0, 0, 128, 64).resizeExact(64, 32, 'triangle').toHdrAsync())
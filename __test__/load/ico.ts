@@ -2,7 +2,7 @@ import {ImageLoader} from "../../index.js"
 import {readFileSync, writeFileSync} from "node:fs";
 
 const buffer = readFileSync("../_source/tree.ico")
-const img = ImageLoader.fromIco([...buffer])
+const img = ImageLoader.fromIco(buffer)
 
 const png = img.toPng()
-writeFileSync("../_out/ico.png", Buffer.from(png))
\ No newline at end of file
+writeFileSync("../_out/ico.png", png)
\ No newline at end of file
@@ -2,7 +2,7 @@ import {ImageLoader} from "../../index.js"
 import {readFileSync, writeFileSync} from "node:fs";
 
 const buffer = readFileSync("../_source/tree.jpeg")
-const img = ImageLoader.fromJpeg([...buffer])
+const img = ImageLoader.fromJpeg(buffer)
 
 const png = img.toPng()
-writeFileSync("../_out/jpeg.png", Buffer.from(png))
\ No newline at end of file
+writeFileSync("../_out/jpeg.png", png)
\ No newline at end of file
@@ -0,0 +1,14 @@
+import {ImageLoader} from "../../index.js"
+import {readFileSync, writeFileSync} from "node:fs";
+
+for (const name of ["tree.jpeg", "tree.png", "tree.tiff", "tree.webp", "orientation/tree_6.jpeg"]) {
+    const buffer = readFileSync(`../_source/${name}`)
+    const {exif, xmp, iptc} = ImageLoader.autoGuess(buffer).metadata()
+
+    console.log(name, exif, xmp?.length, iptc?.length)
+}
+
+// keep the metadata when encoding, without the GPS position
+const photo = ImageLoader.fromJpeg(readFileSync("../_source/orientation/tree_6.jpeg"))
+writeFileSync("../_out/metadata_kept.jpeg", photo.toJpeg(90, {keepMetadata: 'all', stripGps: true}))
+writeFileSync("../_out/metadata_kept.png", await photo.toPngAsync({keepMetadata: ['exif', 'icc']}))
@@ -0,0 +1,10 @@
+import {ImageLoader} from "../../index.js"
+import {readFileSync, writeFileSync} from "node:fs";
+
+for (let n = 1; n <= 8; n++) {
+    const buffer = readFileSync(`../_source/orientation/tree_${n}.jpeg`)
+    const img = ImageLoader.fromJpeg(buffer, {autoOrient: true})
+
+    console.log(n, ImageLoader.fromJpeg(buffer).orientation(), img.dimensions())
+    writeFileSync(`../_out/orientation_${n}.png`, img.toPng())
+}
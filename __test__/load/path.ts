@@ -0,0 +1,8 @@
+import {ImageLoader} from "../../index.js"
+
+// read and write files without passing the bytes through js
+const img = ImageLoader.fromPath("../_source/tree.tga")
+img.save("../_out/path.png", {compression: 'best'})
+
+const jpeg = await ImageLoader.fromPathAsync("../_source/tree.jpeg")
+await jpeg.flip().saveAsync("../_out/path.webp")
@@ -3,10 +3,10 @@ import {readFileSync, writeFileSync} from "node:fs";
 
 const load_trans_write = (ext: string) => {
     const buffer = readFileSync(`../_source/tree.${ext}`)
-    const img = ImageLoader.fromPnm([...buffer])
+    const img = ImageLoader.fromPnm(buffer)
 
     const png = img.toPng()
-    writeFileSync(`../_out/${ext}.png`, Buffer.from(png))
+    writeFileSync(`../_out/${ext}.png`, png)
 }
 
 load_trans_write('pbm')  // lose some data
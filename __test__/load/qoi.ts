@@ -2,7 +2,7 @@ import {ImageLoader} from "../../index.js"
 import {readFileSync, writeFileSync} from "node:fs";
 
 const buffer = readFileSync("../_source/tree.qoi")
-const img = ImageLoader.fromQoi([...buffer])
+const img = ImageLoader.fromQoi(buffer)
 
 const png = img.toPng()
-writeFileSync("../_out/qoi.png", Buffer.from(png))
\ No newline at end of file
+writeFileSync("../_out/qoi.png", png)
\ No newline at end of file
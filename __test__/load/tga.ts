@@ -2,7 +2,7 @@ import {ImageLoader} from "../../index.js"
 import {readFileSync, writeFileSync} from "node:fs";
 
 const buffer = readFileSync("../_source/tree.tga")
-const img = ImageLoader.fromTga([...buffer])
+const img = ImageLoader.fromTga(buffer)
 
 const png = img.toPng()
-writeFileSync("../_out/tga.png", Buffer.from(png))
\ No newline at end of file
+writeFileSync("../_out/tga.png", png)
\ No newline at end of file
@@ -2,7 +2,7 @@ import {ImageLoader} from "../../index.js"
 import {readFileSync, writeFileSync} from "node:fs";
 
 const buffer = readFileSync("../_source/tree.tiff")
-const img = ImageLoader.fromTiff([...buffer])
+const img = ImageLoader.fromTiff(buffer)
 
 const png = img.toPng()
-writeFileSync("../_out/tiff.png", Buffer.from(png))
\ No newline at end of file
+writeFileSync("../_out/tiff.png", png)
\ No newline at end of file
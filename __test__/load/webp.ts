@@ -1,9 +1,15 @@
 import {ImageLoader} from "../../index.js"
 import {readFileSync, writeFileSync} from "node:fs";
 
-const buffer = readFileSync("../source/tree.webp")
-const img = ImageLoader.fromWebp([...buffer])
+const buffer = readFileSync("../_source/tree.webp")
+const img = ImageLoader.fromWebp(buffer)
 
 const png = img.toPng()
+writeFileSync("../_out/webp.png", png)
 
-writeFileSync("../out/webp.png", Buffer.from(png))
\ No newline at end of file
+// re-encode losslessly, and from the other sources
+writeFileSync("../_out/webp.webp", img.toWebp())
+for (const name of ["tree.png", "tree.jpeg", "tree.exr"]) {
+    const source = ImageLoader.autoGuess(readFileSync(`../_source/${name}`))
+    writeFileSync(`../_out/${name.replace(".", "_")}.webp`, await source.toWebpAsync({keepMetadata: 'all'}))
+}
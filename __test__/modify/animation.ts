@@ -0,0 +1,17 @@
+import {AnimatedImage, ImageLoader} from "../../index.js"
+import {readFileSync, writeFileSync} from "node:fs";
+
+const buffer = readFileSync("../_source/tree.png")
+const img = ImageLoader.fromPng(buffer)
+
+// a short preview spinning the image by quarters
+const frames = [0, 1, 2, 3].map(quarter => img.rotateQuarter(quarter).resizeToCover(120, 120, 'triangle'))
+const anim = AnimatedImage.fromFrames(frames, [200, 200, 200, 600])
+
+writeFileSync("../_out/animation.gif", anim.toGif({loopCount: 0, colors: 64, dither: true}))
+writeFileSync("../_out/animation_async.gif", await anim.toGifAsync())
+
+// frame-wise transforms keep the timing of the source animation
+const thumbnail = await anim.resizeToFitAsync(64, 64, 'triangle')
+writeFileSync("../_out/animation_thumbnail.png", thumbnail.flip().toApng({loopCount: 2}))
+writeFileSync("../_out/animation_thumbnail.webp", await thumbnail.rotateQuarterAsync(1).then(rotated => rotated.toWebpAsync()))
@@ -0,0 +1,16 @@
+import {ImageLoader} from "../../index.js"
+import {readFileSync, writeFileSync} from "node:fs";
+
+const buffer = readFileSync("../_source/tree.jpeg")
+const img = await ImageLoader.fromJpegAsync(buffer)
+
+// several async ops share the same source image and run in parallel
+const [resized, rotated, cropped] = await Promise.all([
+    img.resizeToFitAsync(200, 200, 'lanczos3'),
+    img.rotateQuarterAsync(1),
+    img.cropAsync(0, 0, 250, 90),
+])
+
+writeFileSync("../_out/resize_async.jpeg", await resized.toJpegAsync(100))
+writeFileSync("../_out/rotate_async.jpeg", await rotated.toJpegAsync(100))
+writeFileSync("../_out/crop_async.jpeg", await cropped.toJpegAsync(100))
@@ -2,8 +2,8 @@ import {ImageLoader} from "../../index.js"
 import {readFileSync, writeFileSync} from "node:fs";
 
 const buffer = readFileSync("../_source/tree.jpeg")
-const img = ImageLoader.fromJpeg([...buffer])
+const img = ImageLoader.fromJpeg(buffer)
 
 const img_rotated = img.rotateQuarter(1)
 
-writeFileSync("../_out/rotate.jpeg", Buffer.from(img_rotated.toJpeg(100)))
\ No newline at end of file
+writeFileSync("../_out/rotate.jpeg", img_rotated.toJpeg(100))
\ No newline at end of file
@@ -0,0 +1,40 @@
+import {readFileSync} from "node:fs";
+import {performance} from "node:perf_hooks";
+import {ImageLoader} from "../index.js"
+
+// upscale the fixture to get a large (~4 MP) image to move across the boundary
+const source = ImageLoader.fromPng(readFileSync(new URL("../__test__/_source/tree.png", import.meta.url)))
+const large = source.resizeExact(2000, 2000).toBmp()
+
+const bench = (name, fn, rounds = 5) => {
+    fn() // warm up
+    const start = performance.now()
+    for (let i = 0; i < rounds; i++) fn()
+    const avg = (performance.now() - start) / rounds
+    console.log(`${name.padEnd(32)} ${avg.toFixed(2)} ms/op`)
+}
+
+console.log(`input: ${(large.length / 1024 / 1024).toFixed(1)} MB bmp\n`)
+
+// what callers had to do before: spread the Buffer into a number array and wrap the returned array again,
+// with the `Array<number>` marshalling of the old signatures, which `yarn bench` builds with the `bench` feature
+if (ImageLoader.fromBmpNumbers) {
+    bench("number array (spread + wrap)", () => {
+        const img = ImageLoader.fromBmpNumbers([...large])
+        Buffer.from(img.toBmpNumbers())
+    })
+} else {
+    console.log("number array: skipped, build with the `bench` feature to compare")
+}
+
+// zero-copy: the Buffer is handed over as is and the encoded bytes come back as a Buffer
+bench("Buffer (zero-copy)", () => {
+    const img = ImageLoader.fromBmp(large)
+    img.toBmp()
+})
+
+const arrayBuffer = large.buffer.slice(large.byteOffset, large.byteOffset + large.length)
+bench("ArrayBuffer (zero-copy)", () => {
+    const img = ImageLoader.fromBmp(arrayBuffer)
+    img.toBmp()
+})
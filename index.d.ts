@@ -3,7 +3,270 @@
 
 /* auto-generated by NAPI-RS */
 
+/** Timing and layout of a frame in an `AnimatedImage` */
+export interface FrameInfo {
+  /** How long the frame is displayed, in milliseconds */
+  delay: number
+  /** How the area of the frame is treated before the next frame is rendered */
+  disposal: 'none'|'background'|'previous'
+  /** The horizontal offset of the frame as stored in the source, in pixels */
+  left: number
+  /** The vertical offset of the frame as stored in the source, in pixels */
+  top: number
+  /** The width of the frame as stored in the source, in pixels */
+  width: number
+  /** The height of the frame as stored in the source, in pixels */
+  height: number
+}
+/** Options for encoding an `AnimatedImage` as a GIF */
+export interface GifOptions {
+  /** How many times the animation is played, `0` plays it forever. default is `0` */
+  loopCount?: number
+  /** The speed of the palette quantization within `1-30`, lower is slower but more accurate. default is `10` */
+  speed?: number
+  /** The maximum number of colors in the palette of each frame within `2-256`. default is `256` */
+  colors?: number
+  /** Whether to apply Floyd-Steinberg dithering when reducing the colors. default is `false` */
+  dither?: boolean
+}
+/** Options for encoding an `AnimatedImage` as an APNG or WebP */
+export interface AnimationOptions {
+  /** How many times the animation is played, `0` plays it forever. default is `0` */
+  loopCount?: number
+}
+/** Options for the encoders that can embed the metadata of the source */
+export interface EncodeOptions {
+  /**
+   * The metadata to embed, nothing is embedded by default
+   *
+   * ---
+   * - `none`: embed nothing
+   * - `icc`: embed the ICC color profile only
+   * - `all`: embed EXIF, XMP, IPTC and the ICC color profile
+   * - an array of `exif`, `xmp`, `iptc` and `icc`
+   *
+   * A JPEG holds EXIF, XMP and IPTC blocks of up to 64 KiB and ICC profiles of up to 255 segments (about 16 MiB), a larger block throws an `ERR_TOO_LARGE` error instead of being dropped
+   */
+  keepMetadata?: 'none'|'icc'|'all'|Array<'exif'|'xmp'|'iptc'|'icc'>
+  /** Whether to remove the GPS position from the embedded EXIF. default is `false` */
+  stripGps?: boolean
+}
+/** Options of {@link CommonImage.rotate} */
+export interface RotateOptions {
+  /** Whether to grow the canvas to fit the whole rotated image, otherwise the corners are cut off. default is `false` */
+  expand?: boolean
+  /** The CSS color of the area not covered by the image, see {@link ImageLoader.blank}. default is `transparent` */
+  background?: string
+  /** The sampling of the rotated pixels. default is `bilinear` */
+  interpolation?: 'nearest'|'bilinear'|'bicubic'
+}
+/** Options for the PNG encoder */
+export interface PngOptions {
+  /** The compression level, trading encoding time for size. default is `default` */
+  compression?: 'fast'|'default'|'best'
+  /**
+   * The filter applied to each row before compression. default is `adaptive`
+   *
+   * ---
+   * `adaptive` picks the best filter per row, `none` is usually the best choice for palette-like images
+   */
+  filter?: 'none'|'sub'|'up'|'avg'|'paeth'|'adaptive'
+  /** The metadata to embed, see {@link EncodeOptions.keepMetadata}. IPTC has no place in PNG */
+  keepMetadata?: 'none'|'icc'|'all'|Array<'exif'|'xmp'|'iptc'|'icc'>
+  /** Whether to remove the GPS position from the embedded EXIF. default is `false` */
+  stripGps?: boolean
+}
+/** Options for the WebP encoder */
+export interface WebpOptions {
+  /**
+   * Whether to encode losslessly. default is `true`
+   *
+   * ---
+   * Lossy encoding needs libwebp, which is not bundled, so `false` throws an `ERR_UNSUPPORTED` error
+   */
+  lossless?: boolean
+  /** The metadata to embed, see {@link EncodeOptions.keepMetadata}. IPTC has no place in WebP */
+  keepMetadata?: 'none'|'icc'|'all'|Array<'exif'|'xmp'|'iptc'|'icc'>
+  /** Whether to remove the GPS position from the embedded EXIF. default is `false` */
+  stripGps?: boolean
+}
+/** Options for the AVIF encoder */
+export interface AvifOptions {
+  /** The quality of the color channels, valid within `1-100`. default is `80` */
+  quality?: number
+  /** The encoding speed, valid within `1-10`, slower encoding results in smaller files. default is `6` */
+  speed?: number
+  /** The quality of the alpha channel, valid within `1-100`. default is the `quality` */
+  alpha?: number
+}
+/** Options for {@link CommonImage.encode}, only the options of the chosen format may be given */
+export interface FormatOptions {
+  /** JPEG and AVIF, valid within `1-100`. default is `80` */
+  quality?: number
+  /** PNG, see {@link PngOptions.compression} */
+  compression?: 'fast'|'default'|'best'
+  /** PNG, see {@link PngOptions.filter} */
+  filter?: 'none'|'sub'|'up'|'avg'|'paeth'|'adaptive'
+  /** WebP, see {@link WebpOptions.lossless} */
+  lossless?: boolean
+  /** AVIF, see {@link AvifOptions.speed} */
+  speed?: number
+  /** AVIF, see {@link AvifOptions.alpha} */
+  alpha?: number
+  /** PBM, PGM and PPM, whether to use binary sample encoding. default is `true` */
+  binarySample?: boolean
+  /** ICO, see {@link CommonImage.toIco} */
+  strategy?: string
+  /** JPEG, PNG and WebP, see {@link EncodeOptions.keepMetadata} */
+  keepMetadata?: 'none'|'icc'|'all'|Array<'exif'|'xmp'|'iptc'|'icc'>
+  /** JPEG, PNG and WebP, see {@link EncodeOptions.stripGps} */
+  stripGps?: boolean
+}
+/** Options used when loading an image */
+export interface LoadOptions {
+  /** The maximum allowed image width in pixels, checked against the header before decoding. The default is no limit */
+  maxWidth?: number
+  /** The maximum allowed image height in pixels, checked against the header before decoding. The default is no limit */
+  maxHeight?: number
+  /** The maximum number of bytes the decoder may allocate. The default is 512 MiB */
+  maxAlloc?: number
+  /** Whether to apply the EXIF orientation to the pixels, so that the image is upright. The default is `false` */
+  autoOrient?: boolean
+}
+/** The GPS position recorded in the EXIF data */
+export interface GpsInfo {
+  /** Latitude in degrees, negative for south */
+  latitude: number
+  /** Longitude in degrees, negative for west */
+  longitude: number
+  /** Altitude in meters, negative for below sea level */
+  altitude?: number
+}
+/** The commonly used EXIF tags, absent tags are `undefined` */
+export interface ExifInfo {
+  /** The manufacturer of the camera */
+  make?: string
+  /** The model of the camera */
+  model?: string
+  /** The lens model */
+  lensModel?: string
+  /** The software used to create the image */
+  software?: string
+  /** The person who created the image */
+  artist?: string
+  /** The copyright notice */
+  copyright?: string
+  /** The date and time the file was changed, in the format of `YYYY:MM:DD HH:MM:SS` */
+  dateTime?: string
+  /** The date and time the image was captured, in the format of `YYYY:MM:DD HH:MM:SS` */
+  dateTimeOriginal?: string
+  /** The orientation within `1-8` */
+  orientation?: number
+  /** The exposure time, in seconds */
+  exposureTime?: number
+  /** The f-number of the aperture */
+  fNumber?: number
+  /** The ISO speed */
+  iso?: number
+  /** The focal length of the lens, in millimeters */
+  focalLength?: number
+  /** The GPS position */
+  gps?: GpsInfo
+}
+/** The metadata of an image as captured from the source, absent blocks are `undefined` */
+export interface ImageMetadata {
+  /** The commonly used EXIF tags */
+  exif?: ExifInfo
+  /** The raw XMP packet, an XML document */
+  xmp?: string
+  /** The raw IPTC-IIM records */
+  iptc?: Buffer
+}
+/** Header information of an image, read without decoding the pixel data */
+export interface ImageInfo {
+  /** The format name, e.g. `png`, `jpeg`, `openexr` */
+  format: string
+  /** The MIME type of the format, e.g. `image/png` */
+  mimeType: string
+  width: number
+  height: number
+  /** The color type the image decodes to, see {@link CommonImage.colorType} */
+  colorType: 'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'|'unknown_since_non_exhaustive'
+  /** Bits per pixel of the decoded image */
+  bpp: number
+  /** The number of frames, `undefined` if the header does not tell it (TIFF and ICO) */
+  frames?: number
+}
 export function sum(a: number, b: number): number
+/** A sequence of frames decoded from an animated GIF, PNG or WebP */
+export class AnimatedImage {
+  /**
+   * Create an `AnimatedImage` from a sequence of images, e.g. to encode them as an animation
+   *
+   * ---
+   * `frames`: The images must have the same dimensions, otherwise an `ERR_INVALID_PARAMETER` error is thrown
+   *
+   * `delays`: How long each frame is displayed, in milliseconds. There must be one delay per frame
+   */
+  static fromFrames(frames: Array<CommonImage>, delays: Array<number>): AnimatedImage
+  /** Get the dimensions of the canvas, in pixels */
+  dimensions(): [width: number, height: number]
+  /** Get the number of frames */
+  frameCount(): number
+  /** Get the frame at `index` as a `CommonImage`, composited onto the full canvas */
+  frame(index: number): CommonImage
+  /** Get all frames as `CommonImage`s, composited onto the full canvas */
+  frames(): Array<CommonImage>
+  /** Get the delay, disposal and offset of the frame at `index` */
+  frameInfo(index: number): FrameInfo
+  /** Resize every frame, see `CommonImage.resizeToFit`. Returns a new animation with the same timing */
+  resizeToFit(nw: number, nh: number, filter?: 'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'): AnimatedImage
+  /** Async version of {@link resizeToFit}, the resize runs on the libuv thread pool */
+  resizeToFitAsync(nw: number, nh: number, filter?: 'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'): Promise<AnimatedImage>
+  /** Resize and crop every frame, see `CommonImage.resizeToCover`. Returns a new animation with the same timing */
+  resizeToCover(nw: number, nh: number, filter?: 'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'): AnimatedImage
+  /** Async version of {@link resizeToCover}, the resize runs on the libuv thread pool */
+  resizeToCoverAsync(nw: number, nh: number, filter?: 'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'): Promise<AnimatedImage>
+  /** Rotate every frame by 90 degrees clockwise, see `CommonImage.rotateQuarter`. Returns a new animation with the same timing */
+  rotateQuarter(quarter: number): AnimatedImage
+  /** Async version of {@link rotateQuarter}, the rotation runs on the libuv thread pool */
+  rotateQuarterAsync(quarter: number): Promise<AnimatedImage>
+  /** Flip every frame horizontally or vertically, see `CommonImage.flip`. Returns a new animation with the same timing */
+  flip(horizontal?: boolean | undefined | null): AnimatedImage
+  /** Async version of {@link flip}, the flip runs on the libuv thread pool */
+  flipAsync(horizontal?: boolean | undefined | null): Promise<AnimatedImage>
+  /**
+   * Crop every frame, see `CommonImage.crop`. Returns a new animation with the same timing
+   *
+   * ---
+   * The rectangle must be fully inside the canvas, otherwise an `ERR_CROP_OUT_OF_BOUNDS` error is thrown
+   */
+  crop(x: number, y: number, width: number, height: number): AnimatedImage
+  /** Async version of {@link crop}, the crop runs on the libuv thread pool */
+  cropAsync(x: number, y: number, width: number, height: number): Promise<AnimatedImage>
+  /**
+   * Encode the frames as an animated GIF and return the encoded bytes as a `Buffer`
+   *
+   * ---
+   * Every frame gets its own palette, an `ERR_INVALID_PARAMETER` error is thrown if an option is out of range
+   */
+  toGif(options?: GifOptions | undefined | null): Buffer
+  /** Async version of {@link toGif}, the encoding runs on the libuv thread pool */
+  toGifAsync(options?: GifOptions | undefined | null): Promise<Buffer>
+  /** Encode the frames as an animated PNG (APNG) and return the encoded bytes as a `Buffer` */
+  toApng(options?: AnimationOptions | undefined | null): Buffer
+  /** Async version of {@link toApng}, the encoding runs on the libuv thread pool */
+  toApngAsync(options?: AnimationOptions | undefined | null): Promise<Buffer>
+  /**
+   * Encode the frames as an animated WebP and return the encoded bytes as a `Buffer`
+   *
+   * ---
+   * The frames are stored losslessly, an `ERR_TOO_LARGE` error is thrown if the canvas exceeds 16384x16384
+   */
+  toWebp(options?: AnimationOptions | undefined | null): Buffer
+  /** Async version of {@link toWebp}, the encoding runs on the libuv thread pool */
+  toWebpAsync(options?: AnimationOptions | undefined | null): Promise<Buffer>
+}
 /** A wrapper around `ImageWrapper` that can be exposed to JavaScript */
 export class CommonImage {
   dimensions(): [width: number, height: number]
@@ -25,8 +288,44 @@ export class CommonImage {
    * - `unknown_since_non_exhaustive`: unknown color type, this should never happen
    */
   colorType(): 'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'|'unknown_since_non_exhaustive'
+  /**
+   * Get the EXIF orientation of the image, `1` if the source has none
+   *
+   * ---
+   * The value is within `1-8`, see the `Orientation` tag of the EXIF specification. It is `1` once the orientation has been applied by the `autoOrient` load option
+   */
+  orientation(): number
+  /**
+   * Get the EXIF, XMP and IPTC metadata captured from the source when the image was loaded
+   *
+   * ---
+   * The metadata is read from JPEG, TIFF, PNG (`eXIf` and `iTXt` chunks) and WebP sources, it is kept through the transforms
+   */
+  metadata(): ImageMetadata
+  /**
+   * Get the ICC color profile embedded in the source, `null` if there is none
+   *
+   * ---
+   * The profile is read from JPEG (`APP2`), PNG (`iCCP`), WebP (`ICCP`) and TIFF sources, it is kept through the transforms until {@link convertToSrgb}
+   */
+  iccProfile(): Buffer | null
   /** Bits per pixel (bpp) refers to the number of bits of information stored per pixel of the image */
   bpp(): number
+  /**
+   * Get the pixel at `(x, y)` as 8-bit `[r, g, b, a]`, whatever the color type of the image
+   *
+   * ---
+   * A position outside the image throws an `ERR_PIXEL_OUT_OF_BOUNDS` error, use {@link toRaw} to read the samples at full depth
+   */
+  getPixel(x: number, y: number): [r: number, g: number, b: number, a: number]
+  /**
+   * Set the pixel at `(x, y)` from 8-bit `[r, g, b, a]`, converted to the color type of the image. Modifies this image in place
+   *
+   * ---
+   * A position outside the image throws an `ERR_PIXEL_OUT_OF_BOUNDS` error, a sample outside `0-255` throws an `ERR_INVALID_PARAMETER` error.
+   * Pending async tasks and frames of an `AnimatedImage` keep the pixels they were started with
+   */
+  putPixel(x: number, y: number, rgba: [r: number, g: number, b: number, a: number]): void
   /**
    * Resize this image using the specified filter algorithm. Returns a new image. The image's aspect ratio is preserved. The image is scaled to the maximum possible size that fits within the bounds specified by `nw` and `nh`.
    *
@@ -42,6 +341,8 @@ export class CommonImage {
    * see {@link resizeToCover} and {@link resizeExact} for other resize strategies
    */
   resizeToFit(nw: number, nh: number, filter?: 'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'): CommonImage
+  /** Async version of {@link resizeToFit}, the resize runs on the libuv thread pool */
+  resizeToFitAsync(nw: number, nh: number, filter?: 'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'): Promise<CommonImage>
   /**
    * Resize this image using the specified filter algorithm. Returns a new image. The image's aspect ratio is preserved. The image is scaled to the maximum possible size that fits within the larger (relative to aspect ratio) of the bounds specified by `nw` and `nh`, then cropped to fit within the bounds specified by `nw` and `nh`.
    *
@@ -57,6 +358,8 @@ export class CommonImage {
    * see {@link resizeToFit} and {@link resizeExact} for other resize strategies
    */
   resizeToCover(nw: number, nh: number, filter?: 'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'): CommonImage
+  /** Async version of {@link resizeToCover}, the resize runs on the libuv thread pool */
+  resizeToCoverAsync(nw: number, nh: number, filter?: 'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'): Promise<CommonImage>
   /**
    * Resize this image using the specified filter algorithm. Returns a new image. Does not preserve aspect ratio. nw and nh are the new image's dimensions.
    *
@@ -72,13 +375,29 @@ export class CommonImage {
    * see {@link resizeToFit} and {@link resizeToCover} for other resize strategies
    */
   resizeExact(nw: number, nh: number, filter?: 'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'): CommonImage
+  /** Async version of {@link resizeExact}, the resize runs on the libuv thread pool */
+  resizeExactAsync(nw: number, nh: number, filter?: 'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'): Promise<CommonImage>
   /**
    * Rotate this image by 90 degrees clockwise. Returns a new image
    *
    * ---
-   * `quarter`: The number of 90-degree clockwise rotations to apply. Valid within `0-3` and should be a `u8`, otherwise it will cause a panic
+   * `quarter`: The number of 90-degree clockwise rotations to apply. Valid within `0-3`, otherwise an `ERR_INVALID_QUARTER` error is thrown
    */
   rotateQuarter(quarter: number): CommonImage
+  /** Async version of {@link rotateQuarter}, the rotation runs on the libuv thread pool */
+  rotateQuarterAsync(quarter: number): Promise<CommonImage>
+  /**
+   * Rotate this image clockwise by any angle around its center, e.g. to deskew a scanned document. Returns a new image
+   *
+   * ---
+   * `degrees`: the angle of the clockwise rotation, a negative angle rotates counterclockwise. Multiples of 90 are exact, like {@link rotateQuarter}
+   *
+   * `options`: whether to expand the canvas, the background color and the interpolation, see {@link RotateOptions}.
+   * A color type without alpha gets an alpha channel when the background is not opaque, e.g. `rgb8` becomes `rgba8` with the default transparent background
+   */
+  rotate(degrees: number, options?: RotateOptions | undefined | null): CommonImage
+  /** Async version of {@link rotate}, the rotation runs on the libuv thread pool */
+  rotateAsync(degrees: number, options?: RotateOptions | undefined | null): Promise<CommonImage>
   /**
    * Flip this image horizontally or vertically. Returns a new image
    *
@@ -86,48 +405,166 @@ export class CommonImage {
    * `horizontal`: whether to flip horizontally, otherwise it will flip vertically. default is `true`
    */
   flip(horizontal?: boolean | undefined | null): CommonImage
-  /** Crop this image. Returns a new image */
+  /** Async version of {@link flip}, the flip runs on the libuv thread pool */
+  flipAsync(horizontal?: boolean | undefined | null): Promise<CommonImage>
+  /**
+   * Fill the whole image with a color. Returns a new image of the same size and color type, e.g. a background to paste onto
+   *
+   * ---
+   * `color`: a CSS color such as `#rrggbbaa`, `rgb(255 0 0 / 50%)` or `white`, see {@link ImageLoader.blank}. Anything else throws an `ERR_INVALID_COLOR` error
+   */
+  fill(color: string): CommonImage
+  /** Async version of {@link fill}, the fill runs on the libuv thread pool */
+  fillAsync(color: string): Promise<CommonImage>
+  /**
+   * Crop this image. Returns a new image
+   *
+   * ---
+   * The rectangle must be fully inside the image, otherwise an `ERR_CROP_OUT_OF_BOUNDS` error is thrown
+   */
   crop(x: number, y: number, width: number, height: number): CommonImage
-  /** Encode this image as a PNG and return the encoded bytes */
-  toPng(): Array<number>
+  /** Async version of {@link crop}, the crop runs on the libuv thread pool */
+  cropAsync(x: number, y: number, width: number, height: number): Promise<CommonImage>
+  /**
+   * Convert the pixels from the embedded ICC profile to sRGB. Returns a new image without the profile
+   *
+   * ---
+   * Call it before resizing and encoding, so that wide gamut sources (e.g. Display P3 or Adobe RGB photos) keep their colors in viewers that assume sRGB.
+   * An image without a profile is returned as is. RGB matrix/TRC and gray TRC profiles are supported, others throw an `ERR_UNSUPPORTED` error
+   */
+  convertToSrgb(): CommonImage
+  /** Async version of {@link convertToSrgb}, the conversion runs on the libuv thread pool */
+  convertToSrgbAsync(): Promise<CommonImage>
+  /**
+   * Convert the pixels to another color type. Returns a new image, the metadata is kept
+   *
+   * ---
+   * Colors are converted to gray by their luminance, and the alpha is dropped for color types without an alpha channel.
+   * Samples are scaled between bit depths, e.g. `l8` for a grayscale thumbnail or `rgb8` before encoding a 16-bit PNG to JPEG. See {@link colorType} for the names
+   */
+  toColorType(colorType: 'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'): CommonImage
+  /** Async version of {@link toColorType}, the conversion runs on the libuv thread pool */
+  toColorTypeAsync(colorType: 'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'): Promise<CommonImage>
+  /**
+   * Get the pixel data as interleaved samples, row by row without padding, e.g. to feed a tensor or an `ImageData`
+   *
+   * ---
+   * `colorType`: convert the pixels to this color type first, the color type of the image is used when absent.
+   * 8-bit types return an `Uint8Array`, 16-bit types an `Uint16Array` and float types a `Float32Array`, see {@link ImageLoader.fromRaw} for the reverse
+   */
+  toRaw(colorType?: 'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'): Uint8Array | Uint16Array | Float32Array
+  /**
+   * Encode this image in the format picked at runtime, e.g. from an HTTP `Accept` header, and return the encoded bytes as a `Buffer`
+   *
+   * ---
+   * `format`: a format name such as `png`, `jpeg`, `webp` or `pam`, or a MIME type such as `image/webp`. An unknown format throws an `ERR_UNSUPPORTED` error
+   *
+   * `options`: the options of the `to*` method of the format, an option that does not apply to it throws an `ERR_INVALID_PARAMETER` error. JPEG is encoded with quality `80` by default
+   */
+  encode(format: string, options?: FormatOptions | undefined | null): Buffer
+  /** Async version of {@link encode}, the encoding runs on the libuv thread pool */
+  encodeAsync(format: string, options?: FormatOptions | undefined | null): Promise<Buffer>
+  /**
+   * Encode this image in the format of the file extension and write it to `path`, without shuttling the bytes through JavaScript
+   *
+   * ---
+   * `options`: see {@link encode}. An unknown extension throws an `ERR_UNSUPPORTED` error, a failed write an `ERR_IO` error
+   */
+  save(path: string, options?: FormatOptions | undefined | null): void
+  /** Async version of {@link save}, the encoding and writing run on the libuv thread pool */
+  saveAsync(path: string, options?: FormatOptions | undefined | null): Promise<void>
+  /**
+   * Encode this image as a PNG and return the encoded bytes as a `Buffer`
+   *
+   * ---
+   * `options.compression` and `options.filter`: trade encoding time for size, an unknown value throws an `ERR_INVALID_PARAMETER` error
+   *
+   * `options.keepMetadata`: the EXIF, XMP and ICC color profile of the source can be embedded, IPTC has no place in PNG
+   */
+  toPng(options?: PngOptions | undefined | null): Buffer
+  /** Async version of {@link toPng}, the encoding runs on the libuv thread pool */
+  toPngAsync(options?: PngOptions | undefined | null): Promise<Buffer>
+  /**
+   * Encode this image as a WebP and return the encoded bytes as a `Buffer`
+   *
+   * ---
+   * `options.lossless`: only lossless encoding is available, `false` throws an `ERR_UNSUPPORTED` error. 16-bit and floating point images are stored as 8-bit
+   *
+   * `options.keepMetadata`: the EXIF, XMP and ICC color profile of the source can be embedded, IPTC has no place in WebP
+   */
+  toWebp(options?: WebpOptions | undefined | null): Buffer
+  /** Async version of {@link toWebp}, the encoding runs on the libuv thread pool */
+  toWebpAsync(options?: WebpOptions | undefined | null): Promise<Buffer>
+  /**
+   * Encode this image as an AVIF and return the encoded bytes as a `Buffer`
+   *
+   * ---
+   * `options.quality`: Valid within `1-100`, otherwise an `ERR_INVALID_QUALITY` error is thrown. `options.speed` and `options.alpha` out of range throw an `ERR_INVALID_PARAMETER` error
+   *
+   * The encoder is only available when built with the `avif` feature, otherwise an `ERR_UNSUPPORTED` error is thrown
+   */
+  toAvif(options?: AvifOptions | undefined | null): Buffer
+  /** Async version of {@link toAvif}, the encoding runs on the libuv thread pool */
+  toAvifAsync(options?: AvifOptions | undefined | null): Promise<Buffer>
   /**
-   * Encode this image as a JPEG(with specified quality) and return the encoded bytes
+   * Encode this image as a JPEG(with specified quality) and return the encoded bytes as a `Buffer`
    *
    * ---
-   * `quality`: Valid within `1-100` and should be a `u8`, otherwise it will cause a panic
+   * `quality`: Valid within `1-100`, otherwise an `ERR_INVALID_QUALITY` error is thrown
+   *
+   * `options.keepMetadata`: the EXIF, XMP, IPTC and ICC color profile of the source can be embedded
    */
-  toJpeg(quality: number): Array<number>
+  toJpeg(quality: number, options?: EncodeOptions | undefined | null): Buffer
+  /** Async version of {@link toJpeg}, the encoding runs on the libuv thread pool */
+  toJpegAsync(quality: number, options?: EncodeOptions | undefined | null): Promise<Buffer>
   /**
-   * Encode this image as a PNM(in variant PBM) and return the encoded bytes
+   * Encode this image as a PNM(in variant PBM) and return the encoded bytes as a `Buffer`
    *
    * ---
    * `binary_sample`: whether to use binary sample encoding, otherwise it will use ascii sample encoding. default is `true` for smaller size
    */
-  toPbm(binarySample?: boolean | undefined | null): Array<number>
+  toPbm(binarySample?: boolean | undefined | null): Buffer
+  /** Async version of {@link toPbm}, the encoding runs on the libuv thread pool */
+  toPbmAsync(binarySample?: boolean | undefined | null): Promise<Buffer>
   /**
-   * Encode this image as a PNM(in variant PGM) and return the encoded bytes
+   * Encode this image as a PNM(in variant PGM) and return the encoded bytes as a `Buffer`
    *
    * ---
    * `binary_sample`: whether to use binary sample encoding, otherwise it will use ascii sample encoding. default is `true` for smaller size
    */
-  toPgm(binarySample?: boolean | undefined | null): Array<number>
+  toPgm(binarySample?: boolean | undefined | null): Buffer
+  /** Async version of {@link toPgm}, the encoding runs on the libuv thread pool */
+  toPgmAsync(binarySample?: boolean | undefined | null): Promise<Buffer>
   /**
-   * Encode this image as a PNM(in variant PPM) and return the encoded bytes
+   * Encode this image as a PNM(in variant PPM) and return the encoded bytes as a `Buffer`
    *
    * ---
    * `binary_sample`: whether to use binary sample encoding, otherwise it will use ascii sample encoding. default is `true` for smaller size
    */
-  toPpm(binarySample?: boolean | undefined | null): Array<number>
-  /** Encode this image as a PNM(extended as PAM) and return the encoded bytes */
-  toPam(): Array<number>
-  /** Encode this image as a GIF and return the encoded bytes */
-  toGif(): Array<number>
+  toPpm(binarySample?: boolean | undefined | null): Buffer
+  /** Async version of {@link toPpm}, the encoding runs on the libuv thread pool */
+  toPpmAsync(binarySample?: boolean | undefined | null): Promise<Buffer>
+  /** Encode this image as a PNM(extended as PAM) and return the encoded bytes as a `Buffer` */
+  toPam(): Buffer
+  /** Async version of {@link toPam}, the encoding runs on the libuv thread pool */
+  toPamAsync(): Promise<Buffer>
+  /**
+   * Encode this image as a PNM with specified subtype and sample encoding(if any), and return the encoded bytes as a `Buffer`
+   *
+   * ---
+   * see `to_pbm`, `to_pgm`, `to_ppm`, `to_pam` for more details, an unknown subtype throws an `ERR_INVALID_PNM_SUBTYPE` error
+   */
+  toPnm(subtype: 'pbm'|'pgm'|'ppm'|'pam', binarySample?: boolean | undefined | null): Buffer
+  /** Encode this image as a GIF and return the encoded bytes as a `Buffer` */
+  toGif(): Buffer
+  /** Async version of {@link toGif}, the encoding runs on the libuv thread pool */
+  toGifAsync(): Promise<Buffer>
   /**
-   * Encode this image as a ICO and return the encoded bytes
+   * Encode this image as a ICO and return the encoded bytes as a `Buffer`
    *
    * ---
    * `strategy`: The strategy used when the width or height of the image exceeds 256. Its value is in the format of '&lt;mode&gt;_&lt;filter&gt;'.
-   * - `undefined`: No conversion is used, will cause a panic if the width or height of the image exceeds 256
+   * - `undefined`: No conversion is used, an `ERR_TOO_LARGE` error is thrown if the width or height of the image exceeds 256
    *
    * 'mode' can be one of the following:
    * - `fit`: The image's aspect ratio is preserved. The image is scaled to the maximum possible size that fits within **256x256**.
@@ -141,52 +578,178 @@ export class CommonImage {
    * - `gaussian`: Gaussian Filter
    * - `lanczos3`: Lanczos with window 3
    */
-  toIco(strategy?: 'fit_nearest'|'fit_triangle'|'fit_catmullRom'|'fit_gaussian'|'fit_lanczos3'|'cover_nearest'|'cover_triangle'|'cover_catmullRom'|'cover_gaussian'|'cover_lanczos3'|'exact_nearest'|'exact_triangle'|'exact_catmullRom'|'exact_gaussian'|'exact_lanczos3'): Array<number>
-  /** Encode this image as a BMP and return the encoded bytes */
-  toBmp(): Array<number>
-  /** Encode this image as a Farbfeld and return the encoded bytes */
-  toFarbfeld(): Array<number>
-  /** Encode this image as a TGA and return the encoded bytes */
-  toTga(): Array<number>
-  /** Encode this image as a OpenExr and return the encoded bytes */
-  toOpenExr(): Array<number>
-  /** Encode this image as a TIFF and return the encoded bytes */
-  toTiff(): Array<number>
-  /** Encode this image as a QOI and return the encoded bytes */
-  toQoi(): Array<number>
+  toIco(strategy?: 'fit_nearest'|'fit_triangle'|'fit_catmullRom'|'fit_gaussian'|'fit_lanczos3'|'cover_nearest'|'cover_triangle'|'cover_catmullRom'|'cover_gaussian'|'cover_lanczos3'|'exact_nearest'|'exact_triangle'|'exact_catmullRom'|'exact_gaussian'|'exact_lanczos3'): Buffer
+  /** Async version of {@link toIco}, the conversion and encoding run on the libuv thread pool */
+  toIcoAsync(strategy?: 'fit_nearest'|'fit_triangle'|'fit_catmullRom'|'fit_gaussian'|'fit_lanczos3'|'cover_nearest'|'cover_triangle'|'cover_catmullRom'|'cover_gaussian'|'cover_lanczos3'|'exact_nearest'|'exact_triangle'|'exact_catmullRom'|'exact_gaussian'|'exact_lanczos3'): Promise<Buffer>
+  /** Encode this image as a BMP and return the encoded bytes as a `Buffer` */
+  toBmp(): Buffer
+  /** Async version of {@link toBmp}, the encoding runs on the libuv thread pool */
+  toBmpAsync(): Promise<Buffer>
+  /** Encode this image as a Farbfeld and return the encoded bytes as a `Buffer` */
+  toFarbfeld(): Buffer
+  /** Async version of {@link toFarbfeld}, the encoding runs on the libuv thread pool */
+  toFarbfeldAsync(): Promise<Buffer>
+  /** Encode this image as a TGA and return the encoded bytes as a `Buffer` */
+  toTga(): Buffer
+  /** Async version of {@link toTga}, the encoding runs on the libuv thread pool */
+  toTgaAsync(): Promise<Buffer>
+  /**
+   * Encode this image as a OpenExr and return the encoded bytes as a `Buffer`
+   *
+   * ---
+   * Integer samples are promoted to 32-bit floating point, within `0-1`
+   */
+  toOpenExr(): Buffer
+  /** Async version of {@link toOpenExr}, the encoding runs on the libuv thread pool */
+  toOpenExrAsync(): Promise<Buffer>
+  /**
+   * Encode this image as a Radiance HDR and return the encoded bytes as a `Buffer`
+   *
+   * ---
+   * The samples are converted to 32-bit floating point RGB, the alpha channel is dropped
+   */
+  toHdr(): Buffer
+  /** Async version of {@link toHdr}, the encoding runs on the libuv thread pool */
+  toHdrAsync(): Promise<Buffer>
+  /** Encode this image as a TIFF and return the encoded bytes as a `Buffer` */
+  toTiff(): Buffer
+  /** Async version of {@link toTiff}, the encoding runs on the libuv thread pool */
+  toTiffAsync(): Promise<Buffer>
+  /** Encode this image as a QOI and return the encoded bytes as a `Buffer` */
+  toQoi(): Buffer
+  /** Async version of {@link toQoi}, the encoding runs on the libuv thread pool */
+  toQoiAsync(): Promise<Buffer>
 }
 /** `ImageLoader` provides several way to load image binary into a `CommonImage` */
 export class ImageLoader {
-  /** Create a `CommonImage` instance from a byte slice. Makes an educated guess about the image format */
-  static autoGuess(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a PNG */
-  static fromPng(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a JPEG */
-  static fromJpeg(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a GIF */
-  static fromGif(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a WEBP */
-  static fromWebp(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a PNM */
-  static fromPnm(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a TIFF */
-  static fromTiff(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a TGA */
-  static fromTga(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a DDS */
-  static fromDds(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a BMP */
-  static fromBmp(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a ICO */
-  static fromIco(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a Radiance HDR */
-  static fromHdr(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a OpenEXR */
-  static fromOpenEXR(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a Farbfeld */
-  static fromFarbfeld(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a AVIF */
-  static fromAvif(buffer: Array<number>): CommonImage
-  /** Create a `CommonImage` instance from a byte slice. Assumes the image is a QOI */
-  static fromQoi(buffer: Array<number>): CommonImage
+  /** Read the format, dimensions and color type of an image from its header, without decoding the pixel data */
+  static probe(buffer: Buffer | Uint8Array | ArrayBuffer): ImageInfo
+  /**
+   * Create a `CommonImage` instance from a file, without reading it into JavaScript first
+   *
+   * ---
+   * The format is sniffed from the content, and taken from the extension when the content has no magic bytes (TGA). A missing or unreadable file throws an `ERR_IO` error
+   */
+  static fromPath(path: string, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromPath}, the reading and decoding run on the libuv thread pool */
+  static fromPathAsync(path: string, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /**
+   * Create a `CommonImage` instance from raw pixel data, e.g. the `data` of an `ImageData` or the output of {@link CommonImage.toRaw}
+   *
+   * ---
+   * The data is tightly packed rows of samples in the given color type, 16-bit and float samples in the platform byte order, so a `Uint16Array` or `Float32Array` can be passed directly.
+   * The length of the data must be exactly `width * height * bytesPerPixel`, otherwise an `ERR_INVALID_PARAMETER` error is thrown
+   */
+  static fromRaw(data: Buffer | Uint8Array | Uint8ClampedArray | Uint16Array | Float32Array | ArrayBuffer, width: number, height: number, colorType: 'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'): CommonImage
+  /**
+   * Create a `CommonImage` instance of the given size and color type, filled with a color. Transparent when `fillColor` is absent
+   *
+   * ---
+   * `fillColor`: a CSS color, one of
+   * - `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`
+   * - `rgb(255, 128, 0)`, `rgba(255, 128, 0, 0.5)` or `rgb(100% 50% 0% / 50%)`
+   * - a named color such as `white` or `rebeccapurple`, or `transparent`
+   *
+   * Anything else throws an `ERR_INVALID_COLOR` error. The alpha is dropped for color types without an alpha channel.
+   * An image larger than 512 MiB throws an `ERR_LIMIT_EXCEEDED` error
+   */
+  static blank(width: number, height: number, colorType: 'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f', fillColor?: string | undefined | null): CommonImage
+  /**
+   * Sniff the format of an image from its magic bytes, without decoding it. Returns the name of the format, or `null` if it is not recognized
+   *
+   * ---
+   * The name is the lowercase suffix of the corresponding `from*` method, see {@link ImageInfo.format}. TGA has no magic bytes and is never detected
+   */
+  static detectFormat(buffer: Buffer | Uint8Array | ArrayBuffer): 'png'|'jpeg'|'gif'|'webp'|'pnm'|'tiff'|'dds'|'bmp'|'ico'|'hdr'|'openexr'|'farbfeld'|'avif'|'qoi' | null
+  /**
+   * Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`, decoded as the given format
+   *
+   * ---
+   * `format`: a format name, file extension or MIME type, e.g. `png`, `.jpg` or `image/webp`. An unknown format throws an `ERR_UNSUPPORTED` error
+   */
+  static fromFormat(buffer: Buffer | Uint8Array | ArrayBuffer, format: string, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromFormat}, the decoding runs on the libuv thread pool */
+  static fromFormatAsync(buffer: Buffer | Uint8Array | ArrayBuffer, format: string, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Makes an educated guess about the image format */
+  static autoGuess(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link autoGuess}, the decoding runs on the libuv thread pool */
+  static autoGuessAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a PNG */
+  static fromPng(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromPng}, the decoding runs on the libuv thread pool */
+  static fromPngAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a JPEG */
+  static fromJpeg(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromJpeg}, the decoding runs on the libuv thread pool */
+  static fromJpegAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a GIF */
+  static fromGif(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromGif}, the decoding runs on the libuv thread pool */
+  static fromGifAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a WEBP */
+  static fromWebp(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromWebp}, the decoding runs on the libuv thread pool */
+  static fromWebpAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a PNM */
+  static fromPnm(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromPnm}, the decoding runs on the libuv thread pool */
+  static fromPnmAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a TIFF */
+  static fromTiff(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromTiff}, the decoding runs on the libuv thread pool */
+  static fromTiffAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a TGA */
+  static fromTga(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromTga}, the decoding runs on the libuv thread pool */
+  static fromTgaAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a DDS */
+  static fromDds(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromDds}, the decoding runs on the libuv thread pool */
+  static fromDdsAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a BMP */
+  static fromBmp(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromBmp}, the decoding runs on the libuv thread pool */
+  static fromBmpAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a ICO */
+  static fromIco(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromIco}, the decoding runs on the libuv thread pool */
+  static fromIcoAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a Radiance HDR */
+  static fromHdr(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromHdr}, the decoding runs on the libuv thread pool */
+  static fromHdrAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a OpenEXR */
+  static fromOpenEXR(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromOpenEXR}, the decoding runs on the libuv thread pool */
+  static fromOpenEXRAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a Farbfeld */
+  static fromFarbfeld(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromFarbfeld}, the decoding runs on the libuv thread pool */
+  static fromFarbfeldAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /**
+   * Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a AVIF
+   *
+   * ---
+   * The decoder is only available when built with the `avif` feature, otherwise an `ERR_UNSUPPORTED` error is thrown.
+   * 10 and 12-bit images are decoded to the 16-bit color types
+   */
+  static fromAvif(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromAvif}, the decoding runs on the libuv thread pool */
+  static fromAvifAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a QOI */
+  static fromQoi(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): CommonImage
+  /** Async version of {@link fromQoi}, the decoding runs on the libuv thread pool */
+  static fromQoiAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<CommonImage>
+  /** Create an `AnimatedImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a GIF */
+  static framesFromGif(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): AnimatedImage
+  /** Async version of {@link framesFromGif}, the decoding runs on the libuv thread pool */
+  static framesFromGifAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<AnimatedImage>
+  /** Create an `AnimatedImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a PNG (APNG) */
+  static framesFromPng(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): AnimatedImage
+  /** Async version of {@link framesFromPng}, the decoding runs on the libuv thread pool */
+  static framesFromPngAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<AnimatedImage>
+  /** Create an `AnimatedImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a WEBP */
+  static framesFromWebp(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): AnimatedImage
+  /** Async version of {@link framesFromWebp}, the decoding runs on the libuv thread pool */
+  static framesFromWebpAsync(buffer: Buffer | Uint8Array | ArrayBuffer, options?: LoadOptions | undefined | null): Promise<AnimatedImage>
 }
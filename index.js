@@ -252,8 +252,9 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { CommonImage, ImageLoader, sum } = nativeBinding
+const { AnimatedImage, CommonImage, ImageLoader, sum } = nativeBinding
 
+module.exports.AnimatedImage = AnimatedImage
 module.exports.CommonImage = CommonImage
 module.exports.ImageLoader = ImageLoader
 module.exports.sum = sum
@@ -0,0 +1,605 @@
+use std::sync::Arc;
+use color_quant::NeuQuant;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::{dither, ColorMap};
+use image::{ColorType, Delay, DynamicImage, Frame, Frames};
+use napi::bindgen_prelude::{AsyncTask, Buffer, ClassInstance};
+use crate::common::{check_crop, check_quarter, check_range, check_size, optional_filter, CommonImage};
+use crate::core::ImageWrapper;
+use crate::error::{decode_error, error, image_error, ErrorCode, Result};
+use crate::probe::{riff_chunk, Disposal, FrameLayout};
+use crate::task::ImageTask;
+
+/// Timing and layout of a frame in an `AnimatedImage`
+#[napi(object)]
+pub struct FrameInfo {
+    /// How long the frame is displayed, in milliseconds
+    pub delay: u32,
+    /// How the area of the frame is treated before the next frame is rendered
+    #[napi(ts_type = "'none'|'background'|'previous'")]
+    pub disposal: String,
+    /// The horizontal offset of the frame as stored in the source, in pixels
+    pub left: u32,
+    /// The vertical offset of the frame as stored in the source, in pixels
+    pub top: u32,
+    /// The width of the frame as stored in the source, in pixels
+    pub width: u32,
+    /// The height of the frame as stored in the source, in pixels
+    pub height: u32,
+}
+
+/// Options for encoding an `AnimatedImage` as a GIF
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct GifOptions {
+    /// How many times the animation is played, `0` plays it forever. default is `0`
+    pub loop_count: Option<u32>,
+    /// The speed of the palette quantization within `1-30`, lower is slower but more accurate. default is `10`
+    pub speed: Option<u32>,
+    /// The maximum number of colors in the palette of each frame within `2-256`. default is `256`
+    pub colors: Option<u32>,
+    /// Whether to apply Floyd-Steinberg dithering when reducing the colors. default is `false`
+    pub dither: Option<bool>,
+}
+
+/// Options for encoding an `AnimatedImage` as an APNG or WebP
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct AnimationOptions {
+    /// How many times the animation is played, `0` plays it forever. default is `0`
+    pub loop_count: Option<u32>,
+}
+
+/// Encode the frames as an animated GIF, each frame is quantized to its own palette
+fn encode_gif(frames: &[AnimationFrame], options: &GifOptions) -> Result<Vec<u8>> {
+    let loop_count = options.loop_count.unwrap_or(0);
+    let speed = options.speed.unwrap_or(10);
+    let colors = options.colors.unwrap_or(256);
+    let dithered = options.dither.unwrap_or(false);
+    check_range("loopCount", loop_count, 0, u16::MAX as u32 + 1)?;
+    check_range("speed", speed, 1, 30)?;
+    check_range("colors", colors, 2, 256)?;
+
+    let mut buf = vec![];
+    {
+        let mut encoder = GifEncoder::new_with_speed(&mut buf, speed as i32);
+        // the gif loop extension counts the repetitions after the first play
+        let repeat = match loop_count {
+            0 => Repeat::Infinite,
+            n => Repeat::Finite((n - 1) as u16),
+        };
+        encoder.set_repeat(repeat).map_err(image_error)?;
+
+        let frames = frames.iter().map(|frame| {
+            let mut canvas = frame.image.to_rgba8();
+            // with at most 256 distinct colors left, the encoder keeps them as an exact palette
+            if dithered || colors < 256 {
+                let quantizer = NeuQuant::new(speed as i32, colors as usize, &canvas);
+                if dithered {
+                    dither(&mut canvas, &quantizer);
+                } else {
+                    canvas.pixels_mut().for_each(|pixel| quantizer.map_color(pixel));
+                }
+            }
+            Frame::from_parts(canvas, 0, 0, frame.delay)
+        });
+        encoder.encode_frames(frames).map_err(image_error)?;
+    }
+    Ok(buf)
+}
+
+/// Get the delay of a frame in whole milliseconds
+fn delay_ms(delay: Delay) -> u32 {
+    let (numer, denom) = delay.numer_denom_ms();
+    (numer as f64 / denom as f64).round() as u32
+}
+
+/// Encode the frames as an animated PNG, every frame covers the full canvas
+fn encode_apng(frames: &[AnimationFrame], options: &AnimationOptions) -> Result<Vec<u8>> {
+    let loop_count = options.loop_count.unwrap_or(0);
+    let (width, height) = frames[0].image.dimensions();
+    let png_error = |err: png::EncodingError| error(ErrorCode::Encode, "Encode failed", err);
+
+    let mut buf = vec![];
+    let mut encoder = png::Encoder::new(&mut buf, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, loop_count).map_err(png_error)?;
+
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    for frame in frames {
+        // fall back to centiseconds for delays that do not fit into 16 bits of milliseconds
+        let delay = delay_ms(frame.delay);
+        let (numer, denom) = match u16::try_from(delay) {
+            Ok(ms) => (ms, 1000),
+            Err(_) => ((delay / 10).min(u16::MAX as u32) as u16, 100),
+        };
+        writer.set_frame_delay(numer, denom).map_err(png_error)?;
+        writer.write_image_data(&frame.image.to_rgba8()).map_err(png_error)?;
+    }
+    writer.finish().map_err(png_error)?;
+
+    Ok(buf)
+}
+
+/// Encode the frames as an animated WebP, every frame is stored losslessly and covers the full canvas
+fn encode_webp(frames: &[AnimationFrame], options: &AnimationOptions) -> Result<Vec<u8>> {
+    let loop_count = options.loop_count.unwrap_or(0);
+    let (width, height) = frames[0].image.dimensions();
+    check_range("loopCount", loop_count, 0, u16::MAX as u32)?;
+    if width > 16384 || height > 16384 {
+        return Err(error(ErrorCode::TooLarge, "Image too large", format!("{}x{} exceeds 16384x16384 of webp format", width, height)));
+    }
+
+    let canvas = [(width - 1).to_le_bytes(), (height - 1).to_le_bytes()];
+    let mut chunks = vec![];
+
+    // VP8X with the animation and alpha flags, then ANIM with a transparent background
+    let mut vp8x = vec![0x12, 0, 0, 0];
+    vp8x.extend_from_slice(&canvas[0][..3]);
+    vp8x.extend_from_slice(&canvas[1][..3]);
+    riff_chunk(&mut chunks, b"VP8X", &vp8x);
+    let mut anim = vec![0; 4];
+    anim.extend_from_slice(&(loop_count as u16).to_le_bytes());
+    riff_chunk(&mut chunks, b"ANIM", &anim);
+
+    for frame in frames {
+        let mut still = vec![];
+        let rgba = frame.image.to_rgba8();
+        WebPEncoder::new_lossless(&mut still).encode(&rgba, width, height, ColorType::Rgba8).map_err(image_error)?;
+
+        // offset 0x0, canvas size, duration, and no blending, followed by the VP8L chunk of the still image
+        let mut anmf = vec![0; 6];
+        anmf.extend_from_slice(&canvas[0][..3]);
+        anmf.extend_from_slice(&canvas[1][..3]);
+        anmf.extend_from_slice(&delay_ms(frame.delay).min(0xFF_FFFF).to_le_bytes()[..3]);
+        anmf.push(0x02);
+        anmf.extend_from_slice(&still[12..]);
+        riff_chunk(&mut chunks, b"ANMF", &anmf);
+    }
+
+    let mut buf = b"RIFF".to_vec();
+    buf.extend_from_slice(&(chunks.len() as u32 + 4).to_le_bytes());
+    buf.extend_from_slice(b"WEBP");
+    buf.extend_from_slice(&chunks);
+    Ok(buf)
+}
+
+/// Apply a transform to every frame, keeping the timing and disposal of each frame
+fn map_frames<F>(frames: &[AnimationFrame], transform: F) -> Vec<AnimationFrame>
+    where F: Fn(&ImageWrapper) -> ImageWrapper
+{
+    frames.iter().map(|frame| {
+        let image = transform(&frame.image);
+        let (width, height) = image.dimensions();
+        AnimationFrame {
+            image: Arc::new(image),
+            delay: frame.delay,
+            layout: FrameLayout { left: 0, top: 0, width, height, disposal: frame.layout.disposal },
+        }
+    }).collect()
+}
+
+/// A single frame of an animation, composited onto the full canvas
+#[derive(Clone)]
+pub struct AnimationFrame {
+    pub image: Arc<ImageWrapper>,
+    pub delay: Delay,
+    pub layout: FrameLayout,
+}
+
+/// A sequence of frames decoded from an animated GIF, PNG or WebP
+#[napi]
+pub struct AnimatedImage {
+    frames: Vec<AnimationFrame>,
+}
+
+impl AnimatedImage {
+    /// Create a new AnimatedImage from frames, there must be at least one frame
+    pub fn new(frames: Vec<AnimationFrame>) -> Self {
+        AnimatedImage { frames }
+    }
+
+    /// Collect the frames from an animation decoder, pairing them with the layouts read from the container.
+    ///
+    /// The composited frames are counted against `max_alloc` as a whole, since each of them holds a full canvas.
+    pub fn collect(frames: Frames, layouts: Option<Vec<FrameLayout>>, max_alloc: Option<u64>) -> Result<Vec<AnimationFrame>> {
+        let layouts = layouts.unwrap_or_default();
+        let mut allocated: u64 = 0;
+        let mut collected = vec![];
+
+        for (index, frame) in frames.enumerate() {
+            let frame = frame.map_err(decode_error)?;
+            let (w, h) = frame.buffer().dimensions();
+
+            allocated += w as u64 * h as u64 * 4;
+            if let Some(max) = max_alloc.filter(|max| allocated > *max) {
+                return Err(error(
+                    ErrorCode::LimitExceeded,
+                    "Limit exceeded",
+                    format!("{} frames of {}x{} exceed the allocation limit of {} bytes", index + 1, w, h, max),
+                ));
+            }
+
+            // the decoder composites every frame onto the full canvas
+            let layout = layouts.get(index).copied().unwrap_or(FrameLayout { left: 0, top: 0, width: w, height: h, disposal: Disposal::None });
+            collected.push(AnimationFrame {
+                delay: frame.delay(),
+                image: Arc::new(ImageWrapper::new(DynamicImage::ImageRgba8(frame.into_buffer()))),
+                layout,
+            });
+        }
+
+        Ok(collected)
+    }
+
+    /// Run a transform on the libuv thread pool, sharing the source frames with the task
+    #[inline]
+    fn transform_async<F>(&self, transform: F) -> AsyncTask<ImageTask<AnimatedImage>>
+        where F: FnOnce(&[AnimationFrame]) -> Result<Vec<AnimationFrame>> + Send + 'static
+    {
+        let frames = self.frames.clone();
+        ImageTask::spawn(move || transform(&frames).map(AnimatedImage::new))
+    }
+
+    /// Encode on the libuv thread pool, sharing the source frames with the task
+    #[inline]
+    fn out_async<F>(&self, encode: F) -> AsyncTask<ImageTask<Buffer>>
+        where F: FnOnce(&[AnimationFrame]) -> Result<Vec<u8>> + Send + 'static
+    {
+        let frames = self.frames.clone();
+        ImageTask::spawn(move || encode(&frames).map(Buffer::from))
+    }
+
+    #[inline]
+    fn get(&self, index: u32) -> Result<&AnimationFrame> {
+        self.frames.get(index as usize).ok_or_else(|| error(
+            ErrorCode::FrameOutOfBounds,
+            "Frame out of bounds",
+            format!("{}, expected 0-{}", index, self.frames.len() - 1),
+        ))
+    }
+}
+
+#[napi]
+impl AnimatedImage {
+    /// Create an `AnimatedImage` from a sequence of images, e.g. to encode them as an animation
+    ///
+    /// ---
+    /// `frames`: The images must have the same dimensions, otherwise an `ERR_INVALID_PARAMETER` error is thrown
+    ///
+    /// `delays`: How long each frame is displayed, in milliseconds. There must be one delay per frame
+    #[napi(factory)]
+    pub fn from_frames(
+        #[napi(ts_arg_type = "Array<CommonImage>")]
+        frames: Vec<ClassInstance<CommonImage>>,
+        delays: Vec<u32>,
+    ) -> Result<Self> {
+        if frames.is_empty() {
+            return Err(error(ErrorCode::InvalidParameter, "Invalid parameter", "an animation needs at least one frame"));
+        }
+        if frames.len() != delays.len() {
+            return Err(error(ErrorCode::InvalidParameter, "Invalid parameter", format!("{} frames with {} delays", frames.len(), delays.len())));
+        }
+
+        let (width, height) = frames[0].shared().dimensions();
+        let mut collected = vec![];
+        for (index, (frame, delay)) in frames.iter().zip(delays).enumerate() {
+            let image = frame.shared();
+            let (w, h) = image.dimensions();
+            if (w, h) != (width, height) {
+                return Err(error(ErrorCode::InvalidParameter, "Invalid parameter", format!("frame {} is {}x{}, expected {}x{}", index, w, h, width, height)));
+            }
+
+            collected.push(AnimationFrame {
+                image,
+                delay: Delay::from_numer_denom_ms(delay, 1),
+                layout: FrameLayout { left: 0, top: 0, width, height, disposal: Disposal::Background },
+            });
+        }
+
+        Ok(Self::new(collected))
+    }
+
+    /// Get the dimensions of the canvas, in pixels
+    #[napi(ts_return_type = "[width: number, height: number]")]
+    pub fn dimensions(&self) -> Vec<u32> {
+        let (w, h) = self.frames[0].image.dimensions();
+        vec![w, h]
+    }
+
+    /// Get the number of frames
+    #[napi]
+    pub fn frame_count(&self) -> u32 {
+        self.frames.len() as u32
+    }
+
+    /// Get the frame at `index` as a `CommonImage`, composited onto the full canvas
+    #[napi]
+    pub fn frame(&self, index: u32) -> Result<CommonImage> {
+        self.get(index).map(|frame| CommonImage::from_shared(Arc::clone(&frame.image)))
+    }
+
+    /// Get all frames as `CommonImage`s, composited onto the full canvas
+    #[napi]
+    pub fn frames(&self) -> Vec<CommonImage> {
+        self.frames.iter().map(|frame| CommonImage::from_shared(Arc::clone(&frame.image))).collect()
+    }
+
+    /// Get the delay, disposal and offset of the frame at `index`
+    #[napi]
+    pub fn frame_info(&self, index: u32) -> Result<FrameInfo> {
+        let frame = self.get(index)?;
+
+        Ok(FrameInfo {
+            delay: delay_ms(frame.delay),
+            disposal: frame.layout.disposal.name().to_string(),
+            left: frame.layout.left,
+            top: frame.layout.top,
+            width: frame.layout.width,
+            height: frame.layout.height,
+        })
+    }
+
+    /// Resize every frame, see `CommonImage.resizeToFit`. Returns a new animation with the same timing
+    #[napi]
+    pub fn resize_to_fit(
+        &self, nw: u32, nh: u32,
+        #[napi(ts_arg_type = "'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'")]
+        filter: Option<String>,
+    ) -> Result<Self> {
+        check_size(nw, nh)?;
+        let filter = optional_filter(filter)?;
+        Ok(Self::new(map_frames(&self.frames, |image| image.resize_to_fit(nw, nh, filter))))
+    }
+
+    /// Async version of {@link resizeToFit}, the resize runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<AnimatedImage>")]
+    pub fn resize_to_fit_async(
+        &self, nw: u32, nh: u32,
+        #[napi(ts_arg_type = "'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'")]
+        filter: Option<String>,
+    ) -> AsyncTask<ImageTask<AnimatedImage>> {
+        self.transform_async(move |frames| {
+            check_size(nw, nh)?;
+            let filter = optional_filter(filter)?;
+            Ok(map_frames(frames, |image| image.resize_to_fit(nw, nh, filter)))
+        })
+    }
+
+    /// Resize and crop every frame, see `CommonImage.resizeToCover`. Returns a new animation with the same timing
+    #[napi]
+    pub fn resize_to_cover(
+        &self, nw: u32, nh: u32,
+        #[napi(ts_arg_type = "'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'")]
+        filter: Option<String>,
+    ) -> Result<Self> {
+        check_size(nw, nh)?;
+        let filter = optional_filter(filter)?;
+        Ok(Self::new(map_frames(&self.frames, |image| image.resize_to_cover(nw, nh, filter))))
+    }
+
+    /// Async version of {@link resizeToCover}, the resize runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<AnimatedImage>")]
+    pub fn resize_to_cover_async(
+        &self, nw: u32, nh: u32,
+        #[napi(ts_arg_type = "'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'")]
+        filter: Option<String>,
+    ) -> AsyncTask<ImageTask<AnimatedImage>> {
+        self.transform_async(move |frames| {
+            check_size(nw, nh)?;
+            let filter = optional_filter(filter)?;
+            Ok(map_frames(frames, |image| image.resize_to_cover(nw, nh, filter)))
+        })
+    }
+
+    /// Rotate every frame by 90 degrees clockwise, see `CommonImage.rotateQuarter`. Returns a new animation with the same timing
+    #[napi]
+    pub fn rotate_quarter(&self, quarter: u32) -> Result<Self> {
+        check_quarter(quarter)?;
+        Ok(Self::new(map_frames(&self.frames, |image| image.rotate(quarter as u8))))
+    }
+
+    /// Async version of {@link rotateQuarter}, the rotation runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<AnimatedImage>")]
+    pub fn rotate_quarter_async(&self, quarter: u32) -> AsyncTask<ImageTask<AnimatedImage>> {
+        self.transform_async(move |frames| {
+            check_quarter(quarter)?;
+            Ok(map_frames(frames, |image| image.rotate(quarter as u8)))
+        })
+    }
+
+    /// Flip every frame horizontally or vertically, see `CommonImage.flip`. Returns a new animation with the same timing
+    #[napi]
+    pub fn flip(&self, horizontal: Option<bool>) -> Self {
+        let horizontal = horizontal.unwrap_or(true);
+        Self::new(map_frames(&self.frames, |image| image.flip(horizontal)))
+    }
+
+    /// Async version of {@link flip}, the flip runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<AnimatedImage>")]
+    pub fn flip_async(&self, horizontal: Option<bool>) -> AsyncTask<ImageTask<AnimatedImage>> {
+        let horizontal = horizontal.unwrap_or(true);
+        self.transform_async(move |frames| Ok(map_frames(frames, |image| image.flip(horizontal))))
+    }
+
+    /// Crop every frame, see `CommonImage.crop`. Returns a new animation with the same timing
+    ///
+    /// ---
+    /// The rectangle must be fully inside the canvas, otherwise an `ERR_CROP_OUT_OF_BOUNDS` error is thrown
+    #[napi]
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Result<Self> {
+        check_crop(&self.frames[0].image, x, y, width, height)?;
+        Ok(Self::new(map_frames(&self.frames, |image| image.crop(x, y, width, height))))
+    }
+
+    /// Async version of {@link crop}, the crop runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<AnimatedImage>")]
+    pub fn crop_async(&self, x: u32, y: u32, width: u32, height: u32) -> AsyncTask<ImageTask<AnimatedImage>> {
+        self.transform_async(move |frames| {
+            check_crop(&frames[0].image, x, y, width, height)?;
+            Ok(map_frames(frames, |image| image.crop(x, y, width, height)))
+        })
+    }
+
+    // ========== ========== ========== ========== ==========
+    // ========== ========== separator! ========== ==========
+    // ========== ========== ========== ========== ==========
+
+    /// Encode the frames as an animated GIF and return the encoded bytes as a `Buffer`
+    ///
+    /// ---
+    /// Every frame gets its own palette, an `ERR_INVALID_PARAMETER` error is thrown if an option is out of range
+    #[napi]
+    pub fn to_gif(&self, options: Option<GifOptions>) -> Result<Buffer> {
+        encode_gif(&self.frames, &options.unwrap_or_default()).map(Buffer::from)
+    }
+
+    /// Async version of {@link toGif}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_gif_async(&self, options: Option<GifOptions>) -> AsyncTask<ImageTask<Buffer>> {
+        self.out_async(move |frames| encode_gif(frames, &options.unwrap_or_default()))
+    }
+
+    /// Encode the frames as an animated PNG (APNG) and return the encoded bytes as a `Buffer`
+    #[napi]
+    pub fn to_apng(&self, options: Option<AnimationOptions>) -> Result<Buffer> {
+        encode_apng(&self.frames, &options.unwrap_or_default()).map(Buffer::from)
+    }
+
+    /// Async version of {@link toApng}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_apng_async(&self, options: Option<AnimationOptions>) -> AsyncTask<ImageTask<Buffer>> {
+        self.out_async(move |frames| encode_apng(frames, &options.unwrap_or_default()))
+    }
+
+    /// Encode the frames as an animated WebP and return the encoded bytes as a `Buffer`
+    ///
+    /// ---
+    /// The frames are stored losslessly, an `ERR_TOO_LARGE` error is thrown if the canvas exceeds 16384x16384
+    #[napi]
+    pub fn to_webp(&self, options: Option<AnimationOptions>) -> Result<Buffer> {
+        encode_webp(&self.frames, &options.unwrap_or_default()).map(Buffer::from)
+    }
+
+    /// Async version of {@link toWebp}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_webp_async(&self, options: Option<AnimationOptions>) -> AsyncTask<ImageTask<Buffer>> {
+        self.out_async(move |frames| encode_webp(frames, &options.unwrap_or_default()))
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use std::io::Cursor;
+    use std::collections::HashSet;
+    use image::codecs::gif::GifDecoder;
+    use image::codecs::png::PngDecoder;
+    use image::codecs::webp::WebPDecoder;
+    use image::{AnimationDecoder, Rgba, RgbaImage};
+    use super::*;
+
+    /// Frames of a horizontal gradient, so that quantization has something to reduce
+    fn frames(delays: &[u32]) -> Vec<AnimationFrame> {
+        delays.iter().enumerate().map(|(i, delay)| {
+            let canvas = RgbaImage::from_fn(64, 8, |x, y| Rgba([x as u8 * 4, y as u8 * 32, i as u8 * 60, 255]));
+            AnimationFrame {
+                image: Arc::new(ImageWrapper::new(DynamicImage::ImageRgba8(canvas))),
+                delay: Delay::from_numer_denom_ms(*delay, 1),
+                layout: FrameLayout { left: 0, top: 0, width: 64, height: 8, disposal: Disposal::Background },
+            }
+        }).collect()
+    }
+
+    fn decode(buffer: &[u8]) -> AnimatedImage {
+        let decoder = GifDecoder::new(Cursor::new(buffer)).unwrap();
+        AnimatedImage::new(AnimatedImage::collect(decoder.into_frames(), None, None).unwrap())
+    }
+
+    #[test]
+    fn gif_roundtrip() {
+        let buffer = encode_gif(&frames(&[100, 250, 40]), &GifOptions::default()).unwrap();
+        let animation = decode(&buffer);
+
+        assert_eq!(animation.frame_count(), 3);
+        assert_eq!(animation.dimensions(), vec![64, 8]);
+        let delays: Vec<u32> = (0..3).map(|i| animation.frame_info(i).unwrap().delay).collect();
+        assert_eq!(delays, vec![100, 250, 40]);
+    }
+
+    #[test]
+    fn gif_loop_count() {
+        let encode = |loop_count| encode_gif(&frames(&[10, 10]), &GifOptions { loop_count: Some(loop_count), ..Default::default() }).unwrap();
+        let netscape = |buffer: &[u8]| buffer.windows(11).position(|w| w == b"NETSCAPE2.0").map(|i| u16::from_le_bytes([buffer[i + 13], buffer[i + 14]]));
+
+        assert_eq!(netscape(&encode(0)), Some(0));
+        assert_eq!(netscape(&encode(3)), Some(2));
+        assert_eq!(netscape(&encode(1)), None);
+    }
+
+    #[test]
+    fn gif_colors() {
+        for dither in [false, true] {
+            let options = GifOptions { colors: Some(8), dither: Some(dither), ..Default::default() };
+            let animation = decode(&encode_gif(&frames(&[10]), &options).unwrap());
+            let frame = animation.frames[0].image.to_rgba8();
+            let colors: HashSet<_> = frame.pixels().collect();
+            assert!(colors.len() <= 8, "{} colors", colors.len());
+        }
+    }
+
+    #[test]
+    fn transforms_keep_timing() {
+        let animation = AnimatedImage::new(frames(&[100, 250, 40]));
+        let delays = |animation: &AnimatedImage| (0..animation.frame_count()).map(|i| animation.frame_info(i).unwrap().delay).collect::<Vec<_>>();
+
+        let resized = animation.resize_to_fit(32, 32, None).unwrap();
+        assert_eq!(resized.dimensions(), vec![32, 4]);
+        assert_eq!(delays(&resized), vec![100, 250, 40]);
+
+        let rotated = animation.rotate_quarter(1).unwrap();
+        assert_eq!(rotated.dimensions(), vec![8, 64]);
+        assert_eq!(rotated.frame_info(2).unwrap().height, 64);
+
+        let cropped = animation.flip(Some(false)).crop(4, 2, 16, 4).unwrap();
+        assert_eq!(cropped.dimensions(), vec![16, 4]);
+        assert_eq!(delays(&cropped), vec![100, 250, 40]);
+
+        assert_eq!(animation.crop(60, 0, 8, 8).err().map(|err| err.status), Some(ErrorCode::CropOutOfBounds));
+    }
+
+    #[test]
+    fn apng_roundtrip() {
+        let buffer = encode_apng(&frames(&[100, 250, 70000]), &AnimationOptions::default()).unwrap();
+        let decoder = PngDecoder::new(Cursor::new(&buffer)).unwrap();
+        assert!(decoder.is_apng());
+        let animation = AnimatedImage::new(AnimatedImage::collect(decoder.apng().into_frames(), None, None).unwrap());
+
+        assert_eq!(animation.frame_count(), 3);
+        let delays: Vec<u32> = (0..3).map(|i| animation.frame_info(i).unwrap().delay).collect();
+        assert_eq!(delays, vec![100, 250, 70000]);
+        assert_eq!(animation.frames[1].image.to_rgba8(), frames(&[0, 0])[1].image.to_rgba8());
+    }
+
+    #[test]
+    fn webp_roundtrip() {
+        let buffer = encode_webp(&frames(&[100, 250, 40]), &AnimationOptions { loop_count: Some(2) }).unwrap();
+        let decoder = WebPDecoder::new(Cursor::new(&buffer)).unwrap();
+        let animation = AnimatedImage::new(AnimatedImage::collect(decoder.into_frames(), None, None).unwrap());
+
+        assert_eq!(animation.frame_count(), 3);
+        assert_eq!(animation.dimensions(), vec![64, 8]);
+        let delays: Vec<u32> = (0..3).map(|i| animation.frame_info(i).unwrap().delay).collect();
+        assert_eq!(delays, vec![100, 250, 40]);
+        assert_eq!(animation.frames[2].image.to_rgba8(), frames(&[0, 0, 0])[2].image.to_rgba8());
+    }
+
+    #[test]
+    fn gif_invalid_options() {
+        let code = |options: GifOptions| encode_gif(&frames(&[10]), &options).err().map(|err| err.status);
+        assert_eq!(code(GifOptions { speed: Some(0), ..Default::default() }), Some(ErrorCode::InvalidParameter));
+        assert_eq!(code(GifOptions { colors: Some(257), ..Default::default() }), Some(ErrorCode::InvalidParameter));
+        assert_eq!(code(GifOptions { loop_count: Some(70000), ..Default::default() }), Some(ErrorCode::InvalidParameter));
+    }
+}
@@ -0,0 +1,225 @@
+use std::io::Cursor;
+use image::error::{DecodingError, LimitError, LimitErrorKind};
+use image::io::Limits;
+use image::{DynamicImage, ImageBuffer, ImageError, ImageFormat};
+use re_rav1d::pixel::{MatrixCoefficients, YUVRange};
+use re_rav1d::{Decoder, Picture, PixelLayout, Plane, PlanarImageComponent, Settings};
+
+fn decoding(err: impl std::fmt::Display) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormat::Avif.into(), err.to_string()))
+}
+
+/// Decode an AVIF with the pure Rust container parser and AV1 decoder, restricted by the given limits.
+///
+/// 8-bit images are decoded to `Rgb8`/`Rgba8`, 10 and 12-bit images to `Rgb16`/`Rgba16`, monochrome images to the luma types
+pub fn decode(buffer: &[u8], limits: Limits) -> Result<DynamicImage, ImageError> {
+    let avif = avif_parse::read_avif(&mut Cursor::new(buffer)).map_err(decoding)?;
+
+    // the sequence header declares the largest frame, check it before the decoder allocates anything
+    let header = avif.primary_item_metadata().map_err(decoding)?;
+    let (w, h) = (header.max_frame_width.get(), header.max_frame_height.get());
+    limits.check_dimensions(w, h)?;
+    let channels = if header.monochrome { 1 } else { 3 } + avif.alpha_item.is_some() as u64;
+    let sample = if header.bit_depth > 8 { 2 } else { 1 };
+    // the output, plus one plane per channel in the decoder, with the chroma at most at full size
+    if limits.max_alloc.is_some_and(|max| w as u64 * h as u64 * channels * 2 * sample > max) {
+        return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::InsufficientMemory)));
+    }
+
+    let color = Planes::decode(avif.primary_item.to_vec())?;
+    let alpha = match avif.alpha_item {
+        Some(item) => Some(Planes::decode(item.to_vec())?),
+        None => None,
+    };
+    if alpha.as_ref().is_some_and(|alpha| (alpha.width, alpha.height) != (color.width, color.height)) {
+        return Err(decoding("the alpha channel does not match the size of the image"));
+    }
+
+    let (width, height) = (color.width, color.height);
+    let monochrome = color.layout == PixelLayout::I400;
+    let (premultiplied, alpha) = (avif.premultiplied_alpha, alpha.as_ref());
+    let eight = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let sixteen = |c: f32| (c.clamp(0.0, 1.0) * 65535.0).round() as u16;
+    let image = match (color.bits > 8, monochrome, alpha.is_some()) {
+        (false, true, false) => ImageBuffer::from_raw(width, height, interleave(&color, alpha, premultiplied, eight)).map(DynamicImage::ImageLuma8),
+        (false, true, true) => ImageBuffer::from_raw(width, height, interleave(&color, alpha, premultiplied, eight)).map(DynamicImage::ImageLumaA8),
+        (false, false, false) => ImageBuffer::from_raw(width, height, interleave(&color, alpha, premultiplied, eight)).map(DynamicImage::ImageRgb8),
+        (false, false, true) => ImageBuffer::from_raw(width, height, interleave(&color, alpha, premultiplied, eight)).map(DynamicImage::ImageRgba8),
+        (true, true, false) => ImageBuffer::from_raw(width, height, interleave(&color, alpha, premultiplied, sixteen)).map(DynamicImage::ImageLuma16),
+        (true, true, true) => ImageBuffer::from_raw(width, height, interleave(&color, alpha, premultiplied, sixteen)).map(DynamicImage::ImageLumaA16),
+        (true, false, false) => ImageBuffer::from_raw(width, height, interleave(&color, alpha, premultiplied, sixteen)).map(DynamicImage::ImageRgb16),
+        (true, false, true) => ImageBuffer::from_raw(width, height, interleave(&color, alpha, premultiplied, sixteen)).map(DynamicImage::ImageRgba16),
+    };
+    image.ok_or_else(|| decoding("the decoded planes do not match the size of the image"))
+}
+
+/// Convert the planes to interleaved samples, straight into the type of the output so that only the output is allocated
+fn interleave<T>(color: &Planes, alpha: Option<&Planes>, premultiplied: bool, convert: impl Fn(f32) -> T) -> Vec<T> {
+    let monochrome = color.layout == PixelLayout::I400;
+    let channels = if monochrome { 1 } else { 3 } + alpha.is_some() as usize;
+    let mut samples = Vec::with_capacity(color.width as usize * color.height as usize * channels);
+    for y in 0..color.height {
+        for x in 0..color.width {
+            let mut rgb = color.rgb(x, y);
+            let a = alpha.map(|alpha| alpha.normalized(PlanarImageComponent::Y, x, y));
+            if let Some(a) = a.filter(|&a| premultiplied && a > 0.0) {
+                rgb = rgb.map(|c| c / a);
+            }
+            let rgb = if monochrome { &rgb[..1] } else { &rgb[..] };
+            samples.extend(rgb.iter().chain(a.as_ref()).map(|&c| convert(c)));
+        }
+    }
+    samples
+}
+
+/// The YUV planes of a decoded AV1 frame, with what is needed to convert them to RGB
+struct Planes {
+    /// the Y, U and V planes with their strides in bytes, only Y for monochrome frames
+    planes: Vec<(Plane, usize)>,
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    bits: usize,
+    full_range: bool,
+    matrix: MatrixCoefficients,
+}
+
+impl Planes {
+    /// Decode a single frame of AV1 data
+    fn decode(data: Vec<u8>) -> Result<Self, ImageError> {
+        let mut settings = Settings::new();
+        // decode on the calling thread, the async loaders already run on the libuv thread pool
+        settings.set_n_threads(1);
+        settings.set_max_frame_delay(1);
+        let mut decoder = Decoder::with_settings(&settings).map_err(decoding)?;
+
+        // `again` means that the decoder holds on to a part of the data, which is sent once a picture is taken out
+        let pending = |sent: Result<(), re_rav1d::Error>| match sent {
+            Ok(()) => Ok(false),
+            Err(err) if err.is_again() => Ok(true),
+            Err(err) => Err(decoding(err)),
+        };
+        let mut sending = pending(decoder.send_data(data, None, None, None))?;
+        loop {
+            match decoder.get_picture() {
+                Ok(picture) => return Ok(Planes::new(picture)),
+                Err(err) if err.is_again() && sending => sending = pending(decoder.send_pending_data())?,
+                // all the data is in the decoder, and it has nothing to show
+                Err(err) if err.is_again() => return Err(decoding("the AV1 data has no frame")),
+                Err(err) => return Err(decoding(err)),
+            }
+        }
+    }
+
+    fn new(picture: Picture) -> Self {
+        let components: &[PlanarImageComponent] = match picture.pixel_layout() {
+            PixelLayout::I400 => &[PlanarImageComponent::Y],
+            _ => &[PlanarImageComponent::Y, PlanarImageComponent::U, PlanarImageComponent::V],
+        };
+        Planes {
+            planes: components.iter().map(|&c| (picture.plane(c), picture.stride(c) as usize)).collect(),
+            width: picture.width(),
+            height: picture.height(),
+            layout: picture.pixel_layout(),
+            bits: picture.bit_depth(),
+            full_range: picture.color_range() == YUVRange::Full,
+            matrix: picture.matrix_coefficients(),
+        }
+    }
+
+    /// Get a sample of a plane, `x` and `y` are luma positions
+    fn sample(&self, component: PlanarImageComponent, x: u32, y: u32) -> u32 {
+        let (x, y) = match (component, self.layout) {
+            (PlanarImageComponent::Y, _) | (_, PixelLayout::I444) => (x, y),
+            (_, PixelLayout::I422) => (x / 2, y),
+            _ => (x / 2, y / 2),
+        };
+        let (plane, stride) = &self.planes[usize::from(component)];
+        let row = y as usize * stride;
+        if self.bits > 8 {
+            let at = row + x as usize * 2;
+            u16::from_ne_bytes([plane[at], plane[at + 1]]) as u32
+        } else {
+            plane[row + x as usize] as u32
+        }
+    }
+
+    /// Get a sample within `0-1` for luma, and within `-0.5-0.5` for chroma
+    fn normalized(&self, component: PlanarImageComponent, x: u32, y: u32) -> f32 {
+        if component == PlanarImageComponent::Y {
+            return self.unit(component, x, y);
+        }
+        let value = self.sample(component, x, y) as f32;
+        let scale = (1 << (self.bits - 8)) as f32;
+        if self.full_range {
+            (value - 128.0 * scale) / ((1 << self.bits) - 1) as f32
+        } else {
+            (value - 128.0 * scale) / (224.0 * scale)
+        }
+    }
+
+    /// Get a sample within `0-1`, with the range of luma, as the G, B and R planes of the identity matrix are
+    fn unit(&self, component: PlanarImageComponent, x: u32, y: u32) -> f32 {
+        let value = self.sample(component, x, y) as f32;
+        if self.full_range {
+            value / ((1 << self.bits) - 1) as f32
+        } else {
+            let scale = (1 << (self.bits - 8)) as f32;
+            (value - 16.0 * scale) / (219.0 * scale)
+        }
+    }
+
+    /// Convert the pixel at `x`, `y` to RGB, monochrome images have the luma in every channel
+    fn rgb(&self, x: u32, y: u32) -> [f32; 3] {
+        let luma = self.normalized(PlanarImageComponent::Y, x, y);
+        if self.layout == PixelLayout::I400 {
+            return [luma; 3];
+        }
+        if self.matrix == MatrixCoefficients::Identity {
+            // the planes hold G, B and R
+            return [self.unit(PlanarImageComponent::V, x, y), luma, self.unit(PlanarImageComponent::U, x, y)];
+        }
+        let cb = self.normalized(PlanarImageComponent::U, x, y);
+        let cr = self.normalized(PlanarImageComponent::V, x, y);
+
+        let (kr, kb) = match self.matrix {
+            MatrixCoefficients::YCgCo => {
+                let t = luma - cb;
+                return [t + cr, luma + cb, t - cr];
+            }
+            MatrixCoefficients::BT709 => (0.2126, 0.0722),
+            MatrixCoefficients::BT470M => (0.30, 0.11),
+            MatrixCoefficients::ST240M => (0.212, 0.087),
+            MatrixCoefficients::BT2020NonConstantLuminance | MatrixCoefficients::BT2020ConstantLuminance => (0.2627, 0.0593),
+            // BT.601, also used when unspecified
+            _ => (0.299, 0.114),
+        };
+        let r = luma + 2.0 * (1.0 - kr) * cr;
+        let b = luma + 2.0 * (1.0 - kb) * cb;
+        let g = (luma - kr * r - kb * b) / (1.0 - kr - kb);
+        [r, g, b]
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use ravif::{ColorModel, Encoder, Img, RGB8};
+    use super::*;
+
+    #[test]
+    fn identity() {
+        // black and white in G, B and R planes, the extremes have to come back exactly
+        let pixels: Vec<RGB8> = (0..64).map(|i| if i % 16 < 8 { RGB8::new(0, 0, 0) } else { RGB8::new(255, 255, 255) }).collect();
+        let encoded = Encoder::new()
+            .with_quality(100.0)
+            .with_speed(10)
+            .with_internal_color_model(ColorModel::RGB)
+            .encode_rgb(Img::new(&pixels[..], 8, 8))
+            .unwrap();
+        let decoded = decode(&encoded.avif_file, Limits::default()).unwrap().to_rgb16();
+        for (expected, pixel) in pixels.iter().zip(decoded.pixels()) {
+            let expected = if expected.r == 0 { 0 } else { 65535 };
+            assert_eq!(pixel.0, [expected; 3]);
+        }
+    }
+}
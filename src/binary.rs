@@ -0,0 +1,86 @@
+use std::ops::Deref;
+use std::ptr;
+use napi::bindgen_prelude::{FromNapiValue, TypeName, Uint8Array};
+use napi::{check_status, sys, Result, ValueType};
+use napi::sys::TypedarrayType;
+
+/// Binary image data handed over from JavaScript, accepts `Buffer`, `Uint8Array` or `ArrayBuffer`, and the bytes of any other typed array.
+///
+/// The underlying memory is borrowed from the JavaScript heap instead of being copied byte by byte,
+/// and it stays alive until this value is dropped, so it can be moved into async tasks.
+pub struct BinaryInput(Uint8Array);
+
+impl FromNapiValue for BinaryInput {
+    unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> Result<Self> {
+        let mut is_array_buffer = false;
+        check_status!(
+            sys::napi_is_arraybuffer(env, napi_val, &mut is_array_buffer),
+            "Failed to check if value is an ArrayBuffer"
+        )?;
+
+        if is_array_buffer {
+            // view the whole ArrayBuffer as an Uint8Array, which shares the same memory
+            let mut length = 0;
+            check_status!(
+                sys::napi_get_arraybuffer_info(env, napi_val, ptr::null_mut(), &mut length),
+                "Failed to get ArrayBuffer info"
+            )?;
+            return uint8_view(env, napi_val, 0, length);
+        }
+
+        let mut is_typed_array = false;
+        check_status!(
+            sys::napi_is_typedarray(env, napi_val, &mut is_typed_array),
+            "Failed to check if value is a TypedArray"
+        )?;
+        if is_typed_array {
+            let (mut kind, mut length, mut array_buffer, mut offset) = (0, 0, ptr::null_mut(), 0);
+            check_status!(
+                sys::napi_get_typedarray_info(env, napi_val, &mut kind, &mut length, ptr::null_mut(), &mut array_buffer, &mut offset),
+                "Failed to get TypedArray info"
+            )?;
+
+            // view the bytes of other typed arrays, e.g. the Uint8ClampedArray of an ImageData or a Float32Array
+            let element = match kind {
+                TypedarrayType::uint8_array => 0,
+                TypedarrayType::int8_array | TypedarrayType::uint8_clamped_array => 1,
+                TypedarrayType::int16_array | TypedarrayType::uint16_array => 2,
+                TypedarrayType::int32_array | TypedarrayType::uint32_array | TypedarrayType::float32_array => 4,
+                _ => 8,
+            };
+            if element != 0 {
+                return uint8_view(env, array_buffer, offset, length * element);
+            }
+        }
+
+        Uint8Array::from_napi_value(env, napi_val).map(Self)
+    }
+}
+
+/// Create an Uint8Array over `length` bytes of an ArrayBuffer, which shares the same memory
+unsafe fn uint8_view(env: sys::napi_env, array_buffer: sys::napi_value, offset: usize, length: usize) -> Result<BinaryInput> {
+    let mut view = ptr::null_mut();
+    check_status!(
+        sys::napi_create_typedarray(env, TypedarrayType::uint8_array, length, array_buffer, offset, &mut view),
+        "Failed to create Uint8Array view of ArrayBuffer"
+    )?;
+    Uint8Array::from_napi_value(env, view).map(BinaryInput)
+}
+
+impl TypeName for BinaryInput {
+    fn type_name() -> &'static str {
+        "Buffer | Uint8Array | ArrayBuffer"
+    }
+
+    fn value_type() -> ValueType {
+        ValueType::Object
+    }
+}
+
+impl Deref for BinaryInput {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
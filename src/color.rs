@@ -0,0 +1,389 @@
+use image::{ColorType, DynamicImage, Rgba, Rgba32FImage};
+
+/// A tone reproduction curve, mapping an encoded channel value to linear light
+#[derive(Clone, Debug, PartialEq)]
+enum Curve {
+    Identity,
+    Gamma(f32),
+    Table(Vec<f32>),
+    /// the parametric function type 4 of ICC, `(a * x + b) ^ g + e` above `d`, `c * x + f` below
+    Parametric { g: f32, a: f32, b: f32, c: f32, d: f32, e: f32, f: f32 },
+}
+
+impl Curve {
+    fn linearize(&self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            Curve::Identity => x,
+            Curve::Gamma(g) => x.powf(*g),
+            Curve::Table(table) => {
+                // linear interpolation between the entries
+                let pos = x * (table.len() - 1) as f32;
+                let i = (pos as usize).min(table.len() - 2);
+                table[i] + (table[i + 1] - table[i]) * (pos - i as f32)
+            }
+            Curve::Parametric { g, a, b, c, d, e, f } => {
+                if x >= *d {
+                    (a * x + b).max(0.0).powf(*g) + e
+                } else {
+                    c * x + f
+                }
+            }
+        }
+    }
+}
+
+/// A 3x3 matrix in row-major order
+type Matrix = [[f64; 3]; 3];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut m = [[0.0; 3]; 3];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    m
+}
+
+fn invert(m: &Matrix) -> Option<Matrix> {
+    let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let det = m[0][0] * cofactor(1, 2, 1, 2) - m[0][1] * cofactor(1, 2, 0, 2) + m[0][2] * cofactor(1, 2, 0, 1);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    Some([
+        [cofactor(1, 2, 1, 2) / det, -cofactor(0, 2, 1, 2) / det, cofactor(0, 1, 1, 2) / det],
+        [-cofactor(1, 2, 0, 2) / det, cofactor(0, 2, 0, 2) / det, -cofactor(0, 1, 0, 2) / det],
+        [cofactor(1, 2, 0, 1) / det, -cofactor(0, 2, 0, 1) / det, cofactor(0, 1, 0, 1) / det],
+    ])
+}
+
+/// The colorants of sRGB adapted to the D50 white of the profile connection space, as columns
+const SRGB_D50: Matrix = [
+    [0.4360747, 0.3850649, 0.1430804],
+    [0.2225045, 0.7168786, 0.0606169],
+    [0.0139322, 0.0971045, 0.7141733],
+];
+
+/// Encode a linear sRGB channel value with the sRGB transfer function
+fn srgb_encode(x: f32) -> f32 {
+    let x = x.clamp(0.0, 1.0);
+    if x <= 0.0031308 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// An ICC profile that can be converted to sRGB, either an RGB matrix/TRC or a gray TRC profile
+#[derive(Debug)]
+pub struct Profile {
+    curves: [Curve; 3],
+    /// from linear source RGB to linear sRGB, `None` for a gray profile
+    matrix: Option<Matrix>,
+}
+
+/// Read a big endian `u32` at `at`
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Read an `s15Fixed16Number` at `at`
+fn s15f16_at(data: &[u8], at: usize) -> Option<f64> {
+    u32_at(data, at).map(|v| v as i32 as f64 / 65536.0)
+}
+
+impl Profile {
+    /// Parse the tags needed for the conversion, `None` if the profile is malformed or needs a lookup table based transform
+    pub fn parse(icc: &[u8]) -> Option<Self> {
+        let tags: Vec<(&[u8], usize, usize)> = (0..u32_at(icc, 128)? as usize)
+            .map(|i| 132 + i * 12)
+            .map(|at| Some((icc.get(at..at + 4)?, u32_at(icc, at + 4)? as usize, u32_at(icc, at + 8)? as usize)))
+            .collect::<Option<_>>()?;
+        let tag = |signature: &[u8]| tags.iter()
+            .find(|(sig, _, _)| *sig == signature)
+            .and_then(|(_, offset, size)| icc.get(*offset..offset + size));
+
+        // the matrix/TRC model only works with an XYZ connection space
+        if icc.get(20..24)? != b"XYZ " {
+            return None;
+        }
+
+        match icc.get(16..20)? {
+            b"RGB " => {
+                let xyz = |signature: &[u8]| {
+                    let data = tag(signature).filter(|data| data.starts_with(b"XYZ "))?;
+                    Some([s15f16_at(data, 8)?, s15f16_at(data, 12)?, s15f16_at(data, 16)?])
+                };
+                let (r, g, b) = (xyz(b"rXYZ")?, xyz(b"gXYZ")?, xyz(b"bXYZ")?);
+                let source = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+
+                Some(Profile {
+                    curves: [curve(tag(b"rTRC")?)?, curve(tag(b"gTRC")?)?, curve(tag(b"bTRC")?)?],
+                    matrix: Some(multiply(&invert(&SRGB_D50)?, &source)),
+                })
+            }
+            b"GRAY" => {
+                let gray = curve(tag(b"kTRC")?)?;
+                Some(Profile { curves: [gray.clone(), gray.clone(), gray], matrix: None })
+            }
+            _ => None,
+        }
+    }
+
+    /// Convert the pixels to sRGB, keeping the color type of the image
+    pub fn to_srgb(&self, image: &DynamicImage) -> DynamicImage {
+        let mut pixels: Rgba32FImage = image.to_rgba32f();
+        let matrix = self.matrix.map(|m| m.map(|row| row.map(|v| v as f32)));
+
+        for pixel in pixels.pixels_mut() {
+            let linear = [0, 1, 2].map(|c| self.curves[c].linearize(pixel[c]));
+            let srgb = match &matrix {
+                Some(m) => [0, 1, 2].map(|r| m[r][0] * linear[0] + m[r][1] * linear[1] + m[r][2] * linear[2]),
+                None => linear,
+            };
+            for c in 0..3 {
+                pixel[c] = srgb_encode(srgb[c]);
+            }
+        }
+
+        let converted = DynamicImage::ImageRgba32F(pixels);
+        match image.color() {
+            ColorType::L8 => converted.to_luma8().into(),
+            ColorType::La8 => converted.to_luma_alpha8().into(),
+            ColorType::Rgb8 => converted.to_rgb8().into(),
+            ColorType::L16 => converted.to_luma16().into(),
+            ColorType::La16 => converted.to_luma_alpha16().into(),
+            ColorType::Rgb16 => converted.to_rgb16().into(),
+            ColorType::Rgba16 => converted.to_rgba16().into(),
+            ColorType::Rgb32F => converted.to_rgb32f().into(),
+            ColorType::Rgba32F => converted,
+            _ => converted.to_rgba8().into(),
+        }
+    }
+}
+
+/// Parse a `curv` or `para` tag
+fn curve(data: &[u8]) -> Option<Curve> {
+    match data.get(0..4)? {
+        b"curv" => {
+            let count = u32_at(data, 8)? as usize;
+            let entry = |i: usize| data.get(12 + i * 2..14 + i * 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+            match count {
+                0 => Some(Curve::Identity),
+                // a u8Fixed8Number gamma
+                1 => Some(Curve::Gamma(entry(0)? as f32 / 256.0)),
+                _ => (0..count).map(|i| entry(i).map(|v| v as f32 / 65535.0)).collect::<Option<_>>().map(Curve::Table),
+            }
+        }
+        b"para" => {
+            let kind = u16::from_be_bytes([*data.get(8)?, *data.get(9)?]);
+            let count = [1, 3, 4, 5, 7].get(kind as usize)?;
+            let p: Vec<f32> = (0..*count).map(|i| s15f16_at(data, 12 + i * 4).map(|v| v as f32)).collect::<Option<_>>()?;
+
+            // every function type is a special case of type 4
+            let (g, a, b) = (p[0], *p.get(1).unwrap_or(&1.0), *p.get(2).unwrap_or(&0.0));
+            let d = if kind == 1 || kind == 2 { if a == 0.0 { 0.0 } else { -b / a } } else { *p.get(4).unwrap_or(&0.0) };
+            Some(match kind {
+                0 => Curve::Gamma(g),
+                1 => Curve::Parametric { g, a, b, c: 0.0, d, e: 0.0, f: 0.0 },
+                2 => Curve::Parametric { g, a, b, c: 0.0, d, e: p[3], f: p[3] },
+                3 => Curve::Parametric { g, a, b, c: p[3], d, e: 0.0, f: 0.0 },
+                _ => Curve::Parametric { g, a, b, c: p[3], d, e: p[5], f: p[6] },
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The named colors of CSS Color Level 4, sorted by name
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff), ("aquamarine", 0x7fffd4), ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc), ("bisque", 0xffe4c4), ("black", 0x000000), ("blanchedalmond", 0xffebcd), ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a), ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0), ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e), ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed), ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff), ("darkblue", 0x00008b), ("darkcyan", 0x008b8b), ("darkgoldenrod", 0xb8860b), ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400), ("darkgrey", 0xa9a9a9), ("darkkhaki", 0xbdb76b), ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f), ("darkorange", 0xff8c00), ("darkorchid", 0x9932cc), ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a), ("darkseagreen", 0x8fbc8f), ("darkslateblue", 0x483d8b), ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f), ("darkturquoise", 0x00ced1), ("darkviolet", 0x9400d3), ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff), ("dimgray", 0x696969), ("dimgrey", 0x696969), ("dodgerblue", 0x1e90ff), ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0), ("forestgreen", 0x228b22), ("fuchsia", 0xff00ff), ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff), ("gold", 0xffd700), ("goldenrod", 0xdaa520), ("gray", 0x808080), ("green", 0x008000),
+    ("greenyellow", 0xadff2f), ("grey", 0x808080), ("honeydew", 0xf0fff0), ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082), ("ivory", 0xfffff0), ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa), ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00), ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6), ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff), ("lightgoldenrodyellow", 0xfafad2), ("lightgray", 0xd3d3d3), ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3), ("lightpink", 0xffb6c1), ("lightsalmon", 0xffa07a), ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899), ("lightslategrey", 0x778899), ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0), ("lime", 0x00ff00), ("limegreen", 0x32cd32), ("linen", 0xfaf0e6), ("magenta", 0xff00ff),
+    ("maroon", 0x800000), ("mediumaquamarine", 0x66cdaa), ("mediumblue", 0x0000cd), ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371), ("mediumslateblue", 0x7b68ee), ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc), ("mediumvioletred", 0xc71585), ("midnightblue", 0x191970), ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1), ("moccasin", 0xffe4b5), ("navajowhite", 0xffdead), ("navy", 0x000080), ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000), ("olivedrab", 0x6b8e23), ("orange", 0xffa500), ("orangered", 0xff4500), ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98), ("paleturquoise", 0xafeeee), ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5), ("peachpuff", 0xffdab9), ("peru", 0xcd853f), ("pink", 0xffc0cb), ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6), ("purple", 0x800080), ("rebeccapurple", 0x663399), ("red", 0xff0000), ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1), ("saddlebrown", 0x8b4513), ("salmon", 0xfa8072), ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee), ("sienna", 0xa0522d), ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb), ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090), ("slategrey", 0x708090), ("snow", 0xfffafa), ("springgreen", 0x00ff7f), ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c), ("teal", 0x008080), ("thistle", 0xd8bfd8), ("tomato", 0xff6347), ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee), ("wheat", 0xf5deb3), ("white", 0xffffff), ("whitesmoke", 0xf5f5f5), ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+/// Parse a CSS color: `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`/`rgba()` with numbers or percentages, a named color or `transparent`.
+/// Returns `None` for anything else, e.g. `hsl()` or `currentcolor`
+pub fn parse_css_color(css: &str) -> Option<Rgba<u8>> {
+    let css = css.trim().to_ascii_lowercase();
+
+    if let Some(hex) = css.strip_prefix('#') {
+        let digits = hex.chars().map(|c| c.to_digit(16).map(|d| d as u8)).collect::<Option<Vec<u8>>>()?;
+        let channels: Vec<u8> = match digits.len() {
+            3 | 4 => digits.iter().map(|d| d * 17).collect(),
+            6 | 8 => digits.chunks(2).map(|d| d[0] * 16 + d[1]).collect(),
+            _ => return None,
+        };
+        return Some(Rgba([channels[0], channels[1], channels[2], channels.get(3).copied().unwrap_or(255)]));
+    }
+
+    if let Some(args) = css.strip_prefix("rgba(").or_else(|| css.strip_prefix("rgb(")) {
+        // both the legacy `rgb(r, g, b, a)` and the modern `rgb(r g b / a)` syntax
+        let args: Vec<&str> = args.strip_suffix(')')?.split(|c: char| c == ',' || c == '/' || c.is_whitespace()).filter(|a| !a.is_empty()).collect();
+        if !(3..=4).contains(&args.len()) {
+            return None;
+        }
+        let channel = |arg: &str, scale: f32| -> Option<u8> {
+            let value = match arg.strip_suffix('%') {
+                Some(percent) => percent.parse::<f32>().ok()? / 100.0 * 255.0,
+                None => arg.parse::<f32>().ok()? * scale,
+            };
+            value.is_finite().then(|| value.clamp(0.0, 255.0).round() as u8)
+        };
+        let alpha = args.get(3).map_or(Some(255), |a| channel(a, 255.0))?;
+        return Some(Rgba([channel(args[0], 1.0)?, channel(args[1], 1.0)?, channel(args[2], 1.0)?, alpha]));
+    }
+
+    if css == "transparent" {
+        return Some(Rgba([0, 0, 0, 0]));
+    }
+    let index = NAMED_COLORS.binary_search_by(|(name, _)| name.cmp(&css.as_str())).ok()?;
+    let [_, r, g, b] = NAMED_COLORS[index].1.to_be_bytes();
+    Some(Rgba([r, g, b, 255]))
+}
+
+#[cfg(test)]
+mod unit_test {
+    use image::{Rgb, RgbImage};
+    use super::*;
+
+    /// An RGB matrix/TRC profile with the given colorants and a parametric sRGB-like curve, or a gray profile with a gamma
+    fn profile(colorants: Option<Matrix>, gamma: f64) -> Vec<u8> {
+        let fixed = |v: f64| ((v * 65536.0).round() as i32).to_be_bytes();
+        let mut tags: Vec<(&[u8; 4], Vec<u8>)> = vec![];
+
+        let curve = [&b"para\0\0\0\0\0\0\0\0"[..], &fixed(gamma)].concat();
+        match colorants {
+            Some(m) => {
+                for (c, sig) in [b"rXYZ", b"gXYZ", b"bXYZ"].into_iter().enumerate() {
+                    tags.push((sig, [&b"XYZ \0\0\0\0"[..], &fixed(m[0][c]), &fixed(m[1][c]), &fixed(m[2][c])].concat()));
+                }
+                for sig in [b"rTRC", b"gTRC", b"bTRC"] {
+                    tags.push((sig, curve.clone()));
+                }
+            }
+            None => tags.push((b"kTRC", curve)),
+        }
+
+        let mut icc = vec![0; 128];
+        icc[16..20].copy_from_slice(if colorants.is_some() { b"RGB " } else { b"GRAY" });
+        icc[20..24].copy_from_slice(b"XYZ ");
+        icc.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+
+        let mut offset = 132 + tags.len() * 12;
+        let mut data = vec![];
+        for (sig, tag) in &tags {
+            icc.extend_from_slice(*sig);
+            icc.extend_from_slice(&(offset as u32).to_be_bytes());
+            icc.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+            offset += tag.len();
+            data.extend_from_slice(tag);
+        }
+        icc.extend_from_slice(&data);
+        let size = icc.len() as u32;
+        icc[0..4].copy_from_slice(&size.to_be_bytes());
+        icc
+    }
+
+    /// The colorants of Display P3 adapted to D50
+    const P3_D50: Matrix = [
+        [0.5151, 0.2920, 0.1571],
+        [0.2412, 0.6922, 0.0666],
+        [-0.0011, 0.0419, 0.7841],
+    ];
+
+    #[test]
+    fn srgb_is_identity() {
+        let profile = Profile::parse(&profile(Some(SRGB_D50), 2.4)).unwrap();
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(16, 16, |x, y| Rgb([x as u8 * 16, y as u8 * 16, 128])));
+        let converted = profile.to_srgb(&image);
+
+        assert_eq!(converted.color(), ColorType::Rgb8);
+        // the curve is a plain gamma instead of the piecewise sRGB curve, so dark values drift a little
+        for (a, b) in converted.to_rgb8().pixels().zip(image.to_rgb8().pixels()) {
+            assert!(a.0.iter().zip(b.0).all(|(a, b)| a.abs_diff(b) <= 12), "{:?} {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn p3_red_is_clipped() {
+        let profile = Profile::parse(&profile(Some(P3_D50), 2.4)).unwrap();
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb([255, 0, 0])));
+        let red = profile.to_srgb(&image).to_rgb8()[(0, 0)];
+
+        // P3 red is outside of sRGB, so green and blue clip at zero while red saturates
+        assert_eq!(red.0[0], 255);
+        assert!(red.0[1] < 10 && red.0[2] < 10, "{:?}", red);
+
+        // white stays white
+        let white = profile.to_srgb(&DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb([255, 255, 255])))).to_rgb8()[(0, 0)];
+        assert!(white.0.iter().all(|v| *v >= 253), "{:?}", white);
+    }
+
+    #[test]
+    fn gray_and_unsupported() {
+        let gray = Profile::parse(&profile(None, 1.0)).unwrap();
+        let image = DynamicImage::ImageLuma8(image::GrayImage::from_pixel(1, 1, image::Luma([128])));
+        let converted = gray.to_srgb(&image);
+        assert_eq!(converted.color(), ColorType::L8);
+        // a linear gray encoded with the sRGB curve gets brighter
+        assert!(converted.to_luma8()[(0, 0)].0[0] > 180);
+
+        // a Lab connection space needs the lookup tables
+        let mut lab = profile(Some(SRGB_D50), 2.2);
+        lab[20..24].copy_from_slice(b"Lab ");
+        assert!(Profile::parse(&lab).is_none());
+
+        let mut truncated = profile(Some(SRGB_D50), 2.2);
+        truncated.truncate(140);
+        assert!(Profile::parse(&truncated).is_none());
+    }
+
+    #[test]
+    fn css_colors() {
+        assert_eq!(parse_css_color("#f80"), Some(Rgba([255, 136, 0, 255])));
+        assert_eq!(parse_css_color("#ff880080"), Some(Rgba([255, 136, 0, 128])));
+        assert_eq!(parse_css_color(" RGB(255, 136, 0) "), Some(Rgba([255, 136, 0, 255])));
+        assert_eq!(parse_css_color("rgba(255, 136, 0, 0.5)"), Some(Rgba([255, 136, 0, 128])));
+        assert_eq!(parse_css_color("rgb(100% 0% 50% / 25%)"), Some(Rgba([255, 0, 128, 64])));
+        assert_eq!(parse_css_color("RebeccaPurple"), Some(Rgba([102, 51, 153, 255])));
+        assert_eq!(parse_css_color("transparent"), Some(Rgba([0, 0, 0, 0])));
+        assert!(NAMED_COLORS.windows(2).all(|w| w[0].0 < w[1].0));
+
+        for invalid in ["", "#ff888", "#ggg", "rgb(1, 2)", "rgb(1, 2, x)", "hsl(0, 100%, 50%)", "bluish"] {
+            assert_eq!(parse_css_color(invalid), None, "{}", invalid);
+        }
+    }
+}
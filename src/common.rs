@@ -1,25 +1,32 @@
+use image::codecs::png::{CompressionType, FilterType as PngFilterType};
 use image::codecs::pnm::{PnmSubtype, SampleEncoding};
 use image::imageops::FilterType;
-use image::{ColorType, ImageOutputFormat};
-use napi::{Error, Result};
-use crate::core::ImageWrapper;
+use std::borrow::Cow;
+use std::sync::Arc;
+use image::{ColorType, ImageFormat, ImageOutputFormat, Rgba};
+use napi::bindgen_prelude::{AsyncTask, Buffer, Either, Either3, Float32Array, Uint16Array, Uint8Array};
+use crate::color::{parse_css_color, Profile};
+use crate::core::{samples, ImageWrapper, Interpolation};
+use crate::error::{error, image_error, io_error, ErrorCode, Result};
+use crate::metadata::{exif_info, ImageMetadata, Keep};
+use crate::task::ImageTask;
 
-fn filter_parser(filter: &str) -> std::result::Result<FilterType, String> {
+fn filter_parser(filter: &str) -> Result<FilterType> {
     match filter {
         "nearest" => Ok(FilterType::Nearest),
         "triangle" => Ok(FilterType::Triangle),
         "catmullRom" => Ok(FilterType::CatmullRom),
         "gaussian" => Ok(FilterType::Gaussian),
         "lanczos3" => Ok(FilterType::Lanczos3),
-        _ => Err(format!("Invalid filter | {}", filter))
+        _ => Err(error(ErrorCode::InvalidFilter, "Invalid filter", filter))
     }
 }
 
-fn strategy_parser(strategy: &str) -> std::result::Result<(&str, FilterType), String> {
+fn strategy_parser(strategy: &str) -> Result<(&str, FilterType)> {
     let parts: Vec<&str> = strategy.split('_').collect();
 
     if parts.len() != 2 {
-        Err(format!("Invalid strategy | {}", strategy))
+        Err(error(ErrorCode::InvalidStrategy, "Invalid strategy", strategy))
     } else {
         match parts[0] {
             "fit" | "cover" | "exact" => {
@@ -29,28 +36,575 @@ fn strategy_parser(strategy: &str) -> std::result::Result<(&str, FilterType), St
                     "catmullRom" => Ok((parts[0], FilterType::CatmullRom)),
                     "gaussian" => Ok((parts[0], FilterType::Gaussian)),
                     "lanczos3" => Ok((parts[0], FilterType::Lanczos3)),
-                    _ => return Err(format!("Invalid strategy | invalid filter: {}", parts[1]))
+                    _ => Err(error(ErrorCode::InvalidStrategy, "Invalid strategy", format!("invalid filter: {}", parts[1])))
                 }
             }
-            _ => Err(format!("Invalid strategy | invalid mode: {}", parts[0]))
+            _ => Err(error(ErrorCode::InvalidStrategy, "Invalid strategy", format!("invalid mode: {}", parts[0])))
         }
     }
 }
 
+/// Get the name of a color type, see `CommonImage::color_type` for the possible values
+pub fn color_type_name(color: ColorType) -> &'static str {
+    match color {
+        ColorType::L8 => "l8",
+        ColorType::La8 => "la8",
+        ColorType::Rgb8 => "rgb8",
+        ColorType::Rgba8 => "rgba8",
+        ColorType::L16 => "l16",
+        ColorType::La16 => "la16",
+        ColorType::Rgb16 => "rgb16",
+        ColorType::Rgba16 => "rgba16",
+        ColorType::Rgb32F => "rgb32f",
+        ColorType::Rgba32F => "rgba32f",
+        _ => "unknown_since_non_exhaustive"
+    }
+}
+
+/// Parse a color type name, the inverse of {@link color_type_name}
+pub fn parse_color_type(name: &str) -> Result<ColorType> {
+    match name {
+        "l8" => Ok(ColorType::L8),
+        "la8" => Ok(ColorType::La8),
+        "rgb8" => Ok(ColorType::Rgb8),
+        "rgba8" => Ok(ColorType::Rgba8),
+        "l16" => Ok(ColorType::L16),
+        "la16" => Ok(ColorType::La16),
+        "rgb16" => Ok(ColorType::Rgb16),
+        "rgba16" => Ok(ColorType::Rgba16),
+        "rgb32f" => Ok(ColorType::Rgb32F),
+        "rgba32f" => Ok(ColorType::Rgba32F),
+        _ => Err(error(ErrorCode::InvalidParameter, "Invalid color type", name)),
+    }
+}
+
+/// Parse an optional filter name, `nearest` is used when absent
+pub fn optional_filter(filter: Option<String>) -> Result<FilterType> {
+    filter.map_or(Ok(FilterType::Nearest), |f| filter_parser(&f))
+}
+
+pub fn check_size(nw: u32, nh: u32) -> Result<()> {
+    if nw == 0 || nh == 0 {
+        Err(error(ErrorCode::ZeroSize, "Zero size", format!("{}x{}", nw, nh)))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn check_quarter(quarter: u32) -> Result<()> {
+    if quarter > 3 {
+        Err(error(ErrorCode::InvalidQuarter, "Invalid quarter", format!("{}, expected 0-3", quarter)))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn check_range(name: &str, value: u32, min: u32, max: u32) -> Result<()> {
+    if !(min..=max).contains(&value) {
+        Err(error(ErrorCode::InvalidParameter, "Invalid parameter", format!("{} {}, expected {}-{}", name, value, min, max)))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_quality(quality: u32) -> Result<()> {
+    if !(1..=100).contains(&quality) {
+        Err(error(ErrorCode::InvalidQuality, "Invalid quality", format!("{}, expected 1-100", quality)))
+    } else {
+        Ok(())
+    }
+}
+
+/// Parse a CSS color, see {@link parse_css_color}
+pub fn parse_color(css: &str) -> Result<Rgba<u8>> {
+    parse_css_color(css).ok_or_else(|| error(ErrorCode::InvalidColor, "Invalid color", css))
+}
+
+fn pixel_out_of_bounds(wrapper: &ImageWrapper, x: u32, y: u32) -> napi::Error<ErrorCode> {
+    let (w, h) = wrapper.dimensions();
+    error(ErrorCode::PixelOutOfBounds, "Pixel out of bounds", format!("({}, {}) is outside {}x{}", x, y, w, h))
+}
+
+pub fn check_crop(wrapper: &ImageWrapper, x: u32, y: u32, w: u32, h: u32) -> Result<()> {
+    let (iw, ih) = wrapper.dimensions();
+
+    check_size(w, h)?;
+    if x as u64 + w as u64 > iw as u64 || y as u64 + h as u64 > ih as u64 {
+        Err(error(
+            ErrorCode::CropOutOfBounds,
+            "Crop out of bounds",
+            format!("{}x{} at ({}, {}) exceeds {}x{}", w, h, x, y, iw, ih),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Convert the image from its embedded ICC profile to sRGB
+fn convert_to_srgb(wrapper: &ImageWrapper) -> Result<ImageWrapper> {
+    let profile = match wrapper.metadata().icc.as_deref() {
+        Some(icc) => Some(Profile::parse(icc).ok_or_else(|| error(
+            ErrorCode::Unsupported,
+            "Unsupported ICC profile",
+            "only RGB matrix/TRC and gray TRC profiles can be converted to sRGB",
+        ))?),
+        None => None,
+    };
+    Ok(wrapper.to_srgb(profile.as_ref()))
+}
+
+/// Options for the encoders that can embed the metadata of the source
+#[napi(object)]
+pub struct EncodeOptions {
+    /// The metadata to embed, nothing is embedded by default
+    ///
+    /// ---
+    /// - `none`: embed nothing
+    /// - `icc`: embed the ICC color profile only
+    /// - `all`: embed EXIF, XMP, IPTC and the ICC color profile
+    /// - an array of `exif`, `xmp`, `iptc` and `icc`
+    ///
+    /// A JPEG holds EXIF, XMP and IPTC blocks of up to 64 KiB and ICC profiles of up to 255 segments (about 16 MiB), a larger block throws an `ERR_TOO_LARGE` error instead of being dropped
+    #[napi(ts_type = "'none'|'icc'|'all'|Array<'exif'|'xmp'|'iptc'|'icc'>")]
+    pub keep_metadata: Option<Either<String, Vec<String>>>,
+    /// Whether to remove the GPS position from the embedded EXIF. default is `false`
+    pub strip_gps: Option<bool>,
+}
+
+impl EncodeOptions {
+    /// Parse the metadata to keep, nothing is kept when the options are absent
+    fn keep(options: Option<EncodeOptions>) -> Result<Keep> {
+        match options {
+            Some(options) => Keep::parse(options.keep_metadata, options.strip_gps),
+            None => Ok(Keep::default()),
+        }
+    }
+}
+
+/// Options of {@link CommonImage.rotate}
+#[napi(object)]
+#[derive(Default)]
+pub struct RotateOptions {
+    /// Whether to grow the canvas to fit the whole rotated image, otherwise the corners are cut off. default is `false`
+    pub expand: Option<bool>,
+    /// The CSS color of the area not covered by the image, see {@link ImageLoader.blank}. default is `transparent`
+    pub background: Option<String>,
+    /// The sampling of the rotated pixels. default is `bilinear`
+    #[napi(ts_type = "'nearest'|'bilinear'|'bicubic'")]
+    pub interpolation: Option<String>,
+}
+
+/// The parsed `RotateOptions`
+struct RotateSettings {
+    expand: bool,
+    background: Rgba<u8>,
+    interpolation: Interpolation,
+}
+
+impl RotateOptions {
+    /// Parse the options, the defaults are used when the options are absent
+    fn parse(degrees: f64, options: Option<RotateOptions>) -> Result<RotateSettings> {
+        let options = options.unwrap_or_default();
+
+        if !degrees.is_finite() {
+            return Err(error(ErrorCode::InvalidParameter, "Invalid parameter", format!("degrees {}, expected a finite number", degrees)));
+        }
+        let interpolation = match options.interpolation.as_deref() {
+            None | Some("bilinear") => Interpolation::Bilinear,
+            Some("nearest") => Interpolation::Nearest,
+            Some("bicubic") => Interpolation::Bicubic,
+            Some(other) => return Err(error(ErrorCode::InvalidParameter, "Invalid parameter", format!("interpolation {}, expected nearest, bilinear or bicubic", other))),
+        };
+
+        Ok(RotateSettings {
+            expand: options.expand.unwrap_or(false),
+            background: parse_color(options.background.as_deref().unwrap_or("transparent"))?,
+            interpolation,
+        })
+    }
+}
+
+/// Rotate by any angle with the given settings
+fn rotate(wrapper: &ImageWrapper, degrees: f64, settings: RotateSettings) -> ImageWrapper {
+    wrapper.rotate_by(degrees, settings.expand, settings.background, settings.interpolation)
+}
+
+/// Options for the PNG encoder
+#[napi(object)]
+#[derive(Default)]
+pub struct PngOptions {
+    /// The compression level, trading encoding time for size. default is `default`
+    #[napi(ts_type = "'fast'|'default'|'best'")]
+    pub compression: Option<String>,
+    /// The filter applied to each row before compression. default is `adaptive`
+    ///
+    /// ---
+    /// `adaptive` picks the best filter per row, `none` is usually the best choice for palette-like images
+    #[napi(ts_type = "'none'|'sub'|'up'|'avg'|'paeth'|'adaptive'")]
+    pub filter: Option<String>,
+    /// The metadata to embed, see {@link EncodeOptions.keepMetadata}. IPTC has no place in PNG
+    #[napi(ts_type = "'none'|'icc'|'all'|Array<'exif'|'xmp'|'iptc'|'icc'>")]
+    pub keep_metadata: Option<Either<String, Vec<String>>>,
+    /// Whether to remove the GPS position from the embedded EXIF. default is `false`
+    pub strip_gps: Option<bool>,
+}
+
+/// The parsed `PngOptions`
+struct PngSettings {
+    compression: CompressionType,
+    filter: PngFilterType,
+    keep: Keep,
+}
+
+impl PngOptions {
+    /// Parse the options, the defaults are used when the options are absent
+    fn parse(options: Option<PngOptions>) -> Result<PngSettings> {
+        let options = options.unwrap_or_default();
+
+        let compression = match options.compression.as_deref() {
+            None | Some("default") => CompressionType::Default,
+            Some("fast") => CompressionType::Fast,
+            Some("best") => CompressionType::Best,
+            Some(other) => return Err(error(ErrorCode::InvalidParameter, "Invalid parameter", format!("compression {}, expected fast, default or best", other))),
+        };
+        let filter = match options.filter.as_deref() {
+            None | Some("adaptive") => PngFilterType::Adaptive,
+            Some("none") => PngFilterType::NoFilter,
+            Some("sub") => PngFilterType::Sub,
+            Some("up") => PngFilterType::Up,
+            Some("avg") => PngFilterType::Avg,
+            Some("paeth") => PngFilterType::Paeth,
+            Some(other) => return Err(error(ErrorCode::InvalidParameter, "Invalid parameter", format!("filter {}, expected none, sub, up, avg, paeth or adaptive", other))),
+        };
+
+        Ok(PngSettings { compression, filter, keep: Keep::parse(options.keep_metadata, options.strip_gps)? })
+    }
+}
+
+/// Encode as a PNG with the given settings and embed the kept metadata of the source
+fn encode_png(wrapper: &ImageWrapper, settings: PngSettings) -> Result<Vec<u8>> {
+    wrapper.png(settings.compression, settings.filter)
+        .map_err(image_error)
+        .and_then(|encoded| wrapper.metadata().embed(encoded, ImageFormat::Png, settings.keep))
+}
+
+/// Options for the WebP encoder
+#[napi(object)]
+#[derive(Default)]
+pub struct WebpOptions {
+    /// Whether to encode losslessly. default is `true`
+    ///
+    /// ---
+    /// Lossy encoding needs libwebp, which is not bundled, so `false` throws an `ERR_UNSUPPORTED` error
+    pub lossless: Option<bool>,
+    /// The metadata to embed, see {@link EncodeOptions.keepMetadata}. IPTC has no place in WebP
+    #[napi(ts_type = "'none'|'icc'|'all'|Array<'exif'|'xmp'|'iptc'|'icc'>")]
+    pub keep_metadata: Option<Either<String, Vec<String>>>,
+    /// Whether to remove the GPS position from the embedded EXIF. default is `false`
+    pub strip_gps: Option<bool>,
+}
+
+impl WebpOptions {
+    /// Parse the metadata to keep, rejecting the lossy mode
+    fn keep(options: Option<WebpOptions>) -> Result<Keep> {
+        let options = options.unwrap_or_default();
+        if options.lossless == Some(false) {
+            return Err(error(ErrorCode::Unsupported, "Unsupported", "lossy WebP encoding is not available, use lossless"));
+        }
+        Keep::parse(options.keep_metadata, options.strip_gps)
+    }
+}
+
+/// Encode as a lossless WebP and embed the kept metadata of the source
+fn encode_webp(wrapper: &ImageWrapper, keep: Keep) -> Result<Vec<u8>> {
+    wrapper.webp()
+        .map_err(image_error)
+        .and_then(|encoded| wrapper.metadata().embed(encoded, ImageFormat::WebP, keep))
+}
+
+/// Options for the AVIF encoder
+#[napi(object)]
+#[derive(Default)]
+pub struct AvifOptions {
+    /// The quality of the color channels, valid within `1-100`. default is `80`
+    pub quality: Option<u32>,
+    /// The encoding speed, valid within `1-10`, slower encoding results in smaller files. default is `6`
+    pub speed: Option<u32>,
+    /// The quality of the alpha channel, valid within `1-100`. default is the `quality`
+    pub alpha: Option<u32>,
+}
+
+/// The parsed `AvifOptions`, only read by the encoder of the `avif` feature
+#[cfg_attr(not(feature = "avif"), allow(dead_code))]
+struct AvifSettings {
+    quality: u8,
+    speed: u8,
+    alpha: u8,
+}
+
+impl AvifOptions {
+    /// Parse the options, the defaults are used when the options are absent
+    fn parse(options: Option<AvifOptions>) -> Result<AvifSettings> {
+        let options = options.unwrap_or_default();
+        let quality = options.quality.unwrap_or(80);
+        let speed = options.speed.unwrap_or(6);
+        let alpha = options.alpha.unwrap_or(quality);
+        check_quality(quality)?;
+        check_range("speed", speed, 1, 10)?;
+        check_range("alpha", alpha, 1, 100)?;
+
+        Ok(AvifSettings { quality: quality as u8, speed: speed as u8, alpha: alpha as u8 })
+    }
+}
+
+/// Encode as an AVIF with the pure Rust encoder enabled by the `avif` feature
+#[cfg(feature = "avif")]
+fn encode_avif(wrapper: &ImageWrapper, settings: AvifSettings) -> Result<Vec<u8>> {
+    wrapper.avif(settings.quality, settings.alpha, settings.speed).map_err(image_error)
+}
+
+#[cfg(not(feature = "avif"))]
+fn encode_avif(_: &ImageWrapper, _: AvifSettings) -> Result<Vec<u8>> {
+    Err(error(ErrorCode::Unsupported, "Unsupported", "AVIF encoding is not enabled in this build, see the `avif` feature"))
+}
+
+fn encode(wrapper: &ImageWrapper, format: ImageOutputFormat) -> Result<Vec<u8>> {
+    wrapper.buffer(format).map_err(image_error)
+}
+
+/// Encode and embed the kept metadata of the source
+fn encode_with(wrapper: &ImageWrapper, format: ImageOutputFormat, keep: Keep) -> Result<Vec<u8>> {
+    let container = match format {
+        ImageOutputFormat::Jpeg(_) => ImageFormat::Jpeg,
+        _ => return encode(wrapper, format),
+    };
+    encode(wrapper, format).and_then(|encoded| wrapper.metadata().embed(encoded, container, keep))
+}
+
+fn encode_ico(wrapper: &ImageWrapper, strategy: Option<String>) -> Result<Vec<u8>> {
+    let (w, h) = wrapper.dimensions();
+
+    if w <= 256 && h <= 256 {
+        encode(wrapper, ImageOutputFormat::Ico)
+    } else {
+        match strategy {
+            Some(inner) => {
+                let (mode, filter) = strategy_parser(&inner)?;
+                let transferred = match mode {
+                    "fit" => wrapper.resize_to_fit(256, 256, filter),
+                    "cover" => wrapper.resize_to_cover(256, 256, filter),
+                    "exact" => wrapper.resize_exact(256, 256, filter),
+                    _ => return Err(error(ErrorCode::Internal, "Invalid strategy", "This should never happen, please report this issue to me!")),
+                };
+
+                encode(&transferred, ImageOutputFormat::Ico)
+            }
+            None => Err(error(ErrorCode::TooLarge, "Image too large", format!("{}x{} exceeds 256x256 of ico format", w, h))),
+        }
+    }
+}
+
+/// Options for {@link CommonImage.encode}, only the options of the chosen format may be given
+#[napi(object)]
+#[derive(Default)]
+pub struct FormatOptions {
+    /// JPEG and AVIF, valid within `1-100`. default is `80`
+    pub quality: Option<u32>,
+    /// PNG, see {@link PngOptions.compression}
+    #[napi(ts_type = "'fast'|'default'|'best'")]
+    pub compression: Option<String>,
+    /// PNG, see {@link PngOptions.filter}
+    #[napi(ts_type = "'none'|'sub'|'up'|'avg'|'paeth'|'adaptive'")]
+    pub filter: Option<String>,
+    /// WebP, see {@link WebpOptions.lossless}
+    pub lossless: Option<bool>,
+    /// AVIF, see {@link AvifOptions.speed}
+    pub speed: Option<u32>,
+    /// AVIF, see {@link AvifOptions.alpha}
+    pub alpha: Option<u32>,
+    /// PBM, PGM and PPM, whether to use binary sample encoding. default is `true`
+    pub binary_sample: Option<bool>,
+    /// ICO, see {@link CommonImage.toIco}
+    pub strategy: Option<String>,
+    /// JPEG, PNG and WebP, see {@link EncodeOptions.keepMetadata}
+    #[napi(ts_type = "'none'|'icc'|'all'|Array<'exif'|'xmp'|'iptc'|'icc'>")]
+    pub keep_metadata: Option<Either<String, Vec<String>>>,
+    /// JPEG, PNG and WebP, see {@link EncodeOptions.stripGps}
+    pub strip_gps: Option<bool>,
+}
+
+/// The formats {@link CommonImage.encode} can produce
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Target {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+    Avif,
+    Bmp,
+    Ico,
+    Tiff,
+    Tga,
+    Qoi,
+    Farbfeld,
+    OpenExr,
+    Hdr,
+    Pbm,
+    Pgm,
+    Ppm,
+    Pam,
+}
+
+impl Target {
+    /// Parse a format name or MIME type, parameters of the MIME type such as `;q=0.8` are ignored
+    fn parse(format: &str) -> Result<Self> {
+        let name = format.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        let target = match name.strip_prefix("image/").unwrap_or(&name) {
+            "png" | "apng" => Target::Png,
+            "jpeg" | "jpg" | "pjpeg" => Target::Jpeg,
+            "gif" => Target::Gif,
+            "webp" => Target::WebP,
+            "avif" => Target::Avif,
+            "bmp" | "x-bmp" | "x-ms-bmp" => Target::Bmp,
+            "ico" | "x-icon" | "vnd.microsoft.icon" => Target::Ico,
+            "tiff" | "tif" => Target::Tiff,
+            "tga" | "x-tga" | "x-targa" => Target::Tga,
+            "qoi" | "x-qoi" => Target::Qoi,
+            "farbfeld" | "ff" | "x-farbfeld" => Target::Farbfeld,
+            "openexr" | "exr" | "x-exr" => Target::OpenExr,
+            "hdr" | "vnd.radiance" | "x-hdr" => Target::Hdr,
+            "pbm" | "x-portable-bitmap" => Target::Pbm,
+            "pgm" | "x-portable-graymap" => Target::Pgm,
+            "ppm" | "pnm" | "x-portable-pixmap" | "x-portable-anymap" => Target::Ppm,
+            "pam" | "x-portable-arbitrarymap" => Target::Pam,
+            _ => return Err(error(ErrorCode::Unsupported, "Unsupported format", format)),
+        };
+        Ok(target)
+    }
+
+    /// The options that apply to this format
+    fn options(self) -> &'static [&'static str] {
+        match self {
+            Target::Png => &["compression", "filter", "keepMetadata", "stripGps"],
+            Target::Jpeg => &["quality", "keepMetadata", "stripGps"],
+            Target::WebP => &["lossless", "keepMetadata", "stripGps"],
+            Target::Avif => &["quality", "speed", "alpha"],
+            Target::Ico => &["strategy"],
+            Target::Pbm | Target::Pgm | Target::Ppm => &["binarySample"],
+            _ => &[],
+        }
+    }
+
+    /// Reject the options given for another format
+    fn check(self, options: &FormatOptions) -> Result<()> {
+        let given = [
+            ("quality", options.quality.is_some()),
+            ("compression", options.compression.is_some()),
+            ("filter", options.filter.is_some()),
+            ("lossless", options.lossless.is_some()),
+            ("speed", options.speed.is_some()),
+            ("alpha", options.alpha.is_some()),
+            ("binarySample", options.binary_sample.is_some()),
+            ("strategy", options.strategy.is_some()),
+            ("keepMetadata", options.keep_metadata.is_some()),
+            ("stripGps", options.strip_gps.is_some()),
+        ];
+        match given.iter().find(|(name, set)| *set && !self.options().contains(name)) {
+            Some((name, _)) => Err(error(ErrorCode::InvalidParameter, "Invalid parameter", format!("{} does not apply to {:?}", name, self))),
+            None => Ok(()),
+        }
+    }
+
+    /// Encode with the validated options of this format
+    fn encode(self, wrapper: &ImageWrapper, options: Option<FormatOptions>) -> Result<Vec<u8>> {
+        let options = options.unwrap_or_default();
+        self.check(&options)?;
+        let sample = match options.binary_sample {
+            Some(false) => SampleEncoding::Ascii,
+            _ => SampleEncoding::Binary,
+        };
+
+        match self {
+            Target::Png => encode_png(wrapper, PngOptions::parse(Some(PngOptions {
+                compression: options.compression,
+                filter: options.filter,
+                keep_metadata: options.keep_metadata,
+                strip_gps: options.strip_gps,
+            }))?),
+            Target::Jpeg => {
+                let quality = options.quality.unwrap_or(80);
+                check_quality(quality)?;
+                let keep = Keep::parse(options.keep_metadata, options.strip_gps)?;
+                encode_with(wrapper, ImageOutputFormat::Jpeg(quality as u8), keep)
+            }
+            Target::WebP => encode_webp(wrapper, WebpOptions::keep(Some(WebpOptions {
+                lossless: options.lossless,
+                keep_metadata: options.keep_metadata,
+                strip_gps: options.strip_gps,
+            }))?),
+            Target::Avif => encode_avif(wrapper, AvifOptions::parse(Some(AvifOptions {
+                quality: options.quality,
+                speed: options.speed,
+                alpha: options.alpha,
+            }))?),
+            Target::Ico => encode_ico(wrapper, options.strategy),
+            Target::Hdr => wrapper.hdr().map_err(image_error),
+            Target::Gif => encode(wrapper, ImageOutputFormat::Gif),
+            Target::Bmp => encode(wrapper, ImageOutputFormat::Bmp),
+            Target::Tiff => encode(wrapper, ImageOutputFormat::Tiff),
+            Target::Tga => encode(wrapper, ImageOutputFormat::Tga),
+            Target::Qoi => encode(wrapper, ImageOutputFormat::Qoi),
+            Target::Farbfeld => encode(wrapper, ImageOutputFormat::Farbfeld),
+            Target::OpenExr => encode(wrapper, ImageOutputFormat::OpenExr),
+            Target::Pbm => encode(wrapper, ImageOutputFormat::Pnm(PnmSubtype::Bitmap(sample))),
+            Target::Pgm => encode(wrapper, ImageOutputFormat::Pnm(PnmSubtype::Graymap(sample))),
+            Target::Ppm => encode(wrapper, ImageOutputFormat::Pnm(PnmSubtype::Pixmap(sample))),
+            Target::Pam => encode(wrapper, ImageOutputFormat::Pnm(PnmSubtype::ArbitraryMap)),
+        }
+    }
+}
+
+/// Encode in the format of the file extension and write the file
+fn save(wrapper: &ImageWrapper, path: &str, options: Option<FormatOptions>) -> Result<()> {
+    let extension = std::path::Path::new(path).extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    let target = Target::parse(extension).map_err(|_| error(ErrorCode::Unsupported, "Unsupported format", format!("no encoder for the extension of {}", path)))?;
+    let encoded = target.encode(wrapper, options)?;
+    std::fs::write(path, encoded).map_err(|err| io_error(err, path))
+}
+
 /// A wrapper around `ImageWrapper` that can be exposed to JavaScript
 #[napi]
 pub struct CommonImage {
-    /// a wrapper around ImageBuffer that provides dynamic behavior
-    wrapper: ImageWrapper,
+    /// a wrapper around ImageBuffer that provides dynamic behavior, shared with the async tasks spawned from this image
+    wrapper: Arc<ImageWrapper>,
 }
 
 #[napi]
 impl CommonImage {
     /// Create a new CommonImage from a ImageWrapper without exposing the ImageWrapper to JavaScript
     pub fn new(wrapper: ImageWrapper) -> Self {
+        CommonImage { wrapper: Arc::new(wrapper) }
+    }
+
+    /// Create a new CommonImage sharing the ImageWrapper with others, e.g. a frame of an `AnimatedImage`
+    pub fn from_shared(wrapper: Arc<ImageWrapper>) -> Self {
         CommonImage { wrapper }
     }
 
+    /// Get the ImageWrapper shared by this image, without exposing it to JavaScript
+    pub fn shared(&self) -> Arc<ImageWrapper> {
+        Arc::clone(&self.wrapper)
+    }
+
+    /// Run a transform on the libuv thread pool, sharing the source image with the task
+    #[inline]
+    fn transform_async<F>(&self, transform: F) -> AsyncTask<ImageTask<CommonImage>>
+        where F: FnOnce(&ImageWrapper) -> Result<ImageWrapper> + Send + 'static
+    {
+        let source = Arc::clone(&self.wrapper);
+        ImageTask::spawn(move || transform(&source).map(CommonImage::new))
+    }
+
     // ========== ========== ========== ========== ==========
     // ========== ========== separator! ========== ==========
     // ========== ========== ========== ========== ==========
@@ -77,19 +631,40 @@ impl CommonImage {
     /// - `unknown_since_non_exhaustive`: unknown color type, this should never happen
     #[napi(ts_return_type = "'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'|'unknown_since_non_exhaustive'")]
     pub fn color_type(&self) -> String {
-        match self.wrapper.color() {
-            ColorType::L8 => "l8",
-            ColorType::La8 => "la8",
-            ColorType::Rgb8 => "rgb8",
-            ColorType::Rgba8 => "rgba8",
-            ColorType::L16 => "l16",
-            ColorType::La16 => "la16",
-            ColorType::Rgb16 => "rgb16",
-            ColorType::Rgba16 => "rgba16",
-            ColorType::Rgb32F => "rgb32f",
-            ColorType::Rgba32F => "rgba32f",
-            _ => "unknown_since_non_exhaustive"
-        }.to_string()
+        color_type_name(self.wrapper.color()).to_string()
+    }
+
+    /// Get the EXIF orientation of the image, `1` if the source has none
+    ///
+    /// ---
+    /// The value is within `1-8`, see the `Orientation` tag of the EXIF specification. It is `1` once the orientation has been applied by the `autoOrient` load option
+    #[napi]
+    pub fn orientation(&self) -> u32 {
+        self.wrapper.metadata().orientation() as u32
+    }
+
+    /// Get the EXIF, XMP and IPTC metadata captured from the source when the image was loaded
+    ///
+    /// ---
+    /// The metadata is read from JPEG, TIFF, PNG (`eXIf` and `iTXt` chunks) and WebP sources, it is kept through the transforms
+    #[napi]
+    pub fn metadata(&self) -> ImageMetadata {
+        let metadata = self.wrapper.metadata();
+
+        ImageMetadata {
+            exif: metadata.exif.as_deref().and_then(exif_info),
+            xmp: metadata.xmp.as_deref().map(|xmp| String::from_utf8_lossy(xmp).into_owned()),
+            iptc: metadata.iptc.clone().map(Buffer::from),
+        }
+    }
+
+    /// Get the ICC color profile embedded in the source, `null` if there is none
+    ///
+    /// ---
+    /// The profile is read from JPEG (`APP2`), PNG (`iCCP`), WebP (`ICCP`) and TIFF sources, it is kept through the transforms until {@link convertToSrgb}
+    #[napi]
+    pub fn icc_profile(&self) -> Option<Buffer> {
+        self.wrapper.metadata().icc.clone().map(Buffer::from)
     }
 
     /// Bits per pixel (bpp) refers to the number of bits of information stored per pixel of the image
@@ -98,6 +673,42 @@ impl CommonImage {
         self.wrapper.bits_per_pixel()
     }
 
+    /// Get the pixel at `(x, y)` as 8-bit `[r, g, b, a]`, whatever the color type of the image
+    ///
+    /// ---
+    /// A position outside the image throws an `ERR_PIXEL_OUT_OF_BOUNDS` error, use {@link toRaw} to read the samples at full depth
+    #[napi(ts_return_type = "[r: number, g: number, b: number, a: number]")]
+    pub fn get_pixel(&self, x: u32, y: u32) -> Result<Vec<u32>> {
+        let pixel = self.wrapper.pixel(x, y).ok_or_else(|| pixel_out_of_bounds(&self.wrapper, x, y))?;
+        Ok(pixel.0.iter().map(|&c| c as u32).collect())
+    }
+
+    /// Set the pixel at `(x, y)` from 8-bit `[r, g, b, a]`, converted to the color type of the image. Modifies this image in place
+    ///
+    /// ---
+    /// A position outside the image throws an `ERR_PIXEL_OUT_OF_BOUNDS` error, a sample outside `0-255` throws an `ERR_INVALID_PARAMETER` error.
+    /// Pending async tasks and frames of an `AnimatedImage` keep the pixels they were started with
+    #[napi]
+    pub fn put_pixel(
+        &mut self,
+        x: u32,
+        y: u32,
+        #[napi(ts_arg_type = "[r: number, g: number, b: number, a: number]")]
+        rgba: Vec<u32>,
+    ) -> Result<()> {
+        let pixel: [u8; 4] = rgba.iter()
+            .map(|&c| u8::try_from(c).ok())
+            .collect::<Option<Vec<u8>>>()
+            .and_then(|samples| samples.try_into().ok())
+            .ok_or_else(|| error(ErrorCode::InvalidParameter, "Invalid parameter", format!("expected [r, g, b, a] within 0-255, got {:?}", rgba)))?;
+
+        if Arc::make_mut(&mut self.wrapper).put_pixel(x, y, Rgba(pixel)) {
+            Ok(())
+        } else {
+            Err(pixel_out_of_bounds(&self.wrapper, x, y))
+        }
+    }
+
     /// Resize this image using the specified filter algorithm. Returns a new image. The image's aspect ratio is preserved. The image is scaled to the maximum possible size that fits within the bounds specified by `nw` and `nh`.
     ///
     /// ---
@@ -115,11 +726,24 @@ impl CommonImage {
         &self, nw: u32, nh: u32,
         #[napi(ts_arg_type = "'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'")]
         filter: Option<String>,
-    ) -> Self {
-        let filter = filter.map_or(FilterType::Nearest, |f| filter_parser(&f).unwrap());
-        Self {
-            wrapper: self.wrapper.resize_to_fit(nw, nh, filter)
-        }
+    ) -> Result<Self> {
+        check_size(nw, nh)?;
+        let filter = optional_filter(filter)?;
+        Ok(Self::new(self.wrapper.resize_to_fit(nw, nh, filter)))
+    }
+
+    /// Async version of {@link resizeToFit}, the resize runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn resize_to_fit_async(
+        &self, nw: u32, nh: u32,
+        #[napi(ts_arg_type = "'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'")]
+        filter: Option<String>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        self.transform_async(move |wrapper| {
+            check_size(nw, nh)?;
+            let filter = optional_filter(filter)?;
+            Ok(wrapper.resize_to_fit(nw, nh, filter))
+        })
     }
 
     /// Resize this image using the specified filter algorithm. Returns a new image. The image's aspect ratio is preserved. The image is scaled to the maximum possible size that fits within the larger (relative to aspect ratio) of the bounds specified by `nw` and `nh`, then cropped to fit within the bounds specified by `nw` and `nh`.
@@ -139,11 +763,24 @@ impl CommonImage {
         &self, nw: u32, nh: u32,
         #[napi(ts_arg_type = "'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'")]
         filter: Option<String>,
-    ) -> Self {
-        let filter = filter.map_or(FilterType::Nearest, |f| filter_parser(&f).unwrap());
-        Self {
-            wrapper: self.wrapper.resize_to_cover(nw, nh, filter)
-        }
+    ) -> Result<Self> {
+        check_size(nw, nh)?;
+        let filter = optional_filter(filter)?;
+        Ok(Self::new(self.wrapper.resize_to_cover(nw, nh, filter)))
+    }
+
+    /// Async version of {@link resizeToCover}, the resize runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn resize_to_cover_async(
+        &self, nw: u32, nh: u32,
+        #[napi(ts_arg_type = "'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'")]
+        filter: Option<String>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        self.transform_async(move |wrapper| {
+            check_size(nw, nh)?;
+            let filter = optional_filter(filter)?;
+            Ok(wrapper.resize_to_cover(nw, nh, filter))
+        })
     }
 
     /// Resize this image using the specified filter algorithm. Returns a new image. Does not preserve aspect ratio. nw and nh are the new image's dimensions.
@@ -163,22 +800,63 @@ impl CommonImage {
         &self, nw: u32, nh: u32,
         #[napi(ts_arg_type = "'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'")]
         filter: Option<String>,
-    ) -> Self {
-        let filter = filter.map_or(FilterType::Nearest, |f| filter_parser(&f).unwrap());
-        Self {
-            wrapper: self.wrapper.resize_exact(nw, nh, filter)
-        }
+    ) -> Result<Self> {
+        check_size(nw, nh)?;
+        let filter = optional_filter(filter)?;
+        Ok(Self::new(self.wrapper.resize_exact(nw, nh, filter)))
+    }
+
+    /// Async version of {@link resizeExact}, the resize runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn resize_exact_async(
+        &self, nw: u32, nh: u32,
+        #[napi(ts_arg_type = "'nearest'|'triangle'|'catmullRom'|'gaussian'|'lanczos3'")]
+        filter: Option<String>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        self.transform_async(move |wrapper| {
+            check_size(nw, nh)?;
+            let filter = optional_filter(filter)?;
+            Ok(wrapper.resize_exact(nw, nh, filter))
+        })
     }
 
     /// Rotate this image by 90 degrees clockwise. Returns a new image
     ///
     /// ---
-    /// `quarter`: The number of 90-degree clockwise rotations to apply. Valid within `0-3` and should be a `u8`, otherwise it will cause a panic
+    /// `quarter`: The number of 90-degree clockwise rotations to apply. Valid within `0-3`, otherwise an `ERR_INVALID_QUARTER` error is thrown
     #[napi]
-    pub fn rotate_quarter(&self, quarter: u8) -> Self {
-        Self {
-            wrapper: self.wrapper.rotate(quarter)
-        }
+    pub fn rotate_quarter(&self, quarter: u32) -> Result<Self> {
+        check_quarter(quarter)?;
+        Ok(Self::new(self.wrapper.rotate(quarter as u8)))
+    }
+
+    /// Async version of {@link rotateQuarter}, the rotation runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn rotate_quarter_async(&self, quarter: u32) -> AsyncTask<ImageTask<CommonImage>> {
+        self.transform_async(move |wrapper| {
+            check_quarter(quarter)?;
+            Ok(wrapper.rotate(quarter as u8))
+        })
+    }
+
+    /// Rotate this image clockwise by any angle around its center, e.g. to deskew a scanned document. Returns a new image
+    ///
+    /// ---
+    /// `degrees`: the angle of the clockwise rotation, a negative angle rotates counterclockwise. Multiples of 90 are exact, like {@link rotateQuarter}
+    ///
+    /// `options`: whether to expand the canvas, the background color and the interpolation, see {@link RotateOptions}.
+    /// A color type without alpha gets an alpha channel when the background is not opaque, e.g. `rgb8` becomes `rgba8` with the default transparent background
+    #[napi]
+    pub fn rotate(&self, degrees: f64, options: Option<RotateOptions>) -> Result<Self> {
+        let settings = RotateOptions::parse(degrees, options)?;
+        Ok(Self::new(rotate(&self.wrapper, degrees, settings)))
+    }
+
+    /// Async version of {@link rotate}, the rotation runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn rotate_async(&self, degrees: f64, options: Option<RotateOptions>) -> AsyncTask<ImageTask<CommonImage>> {
+        let settings = RotateOptions::parse(degrees, options);
+        self.transform_async(move |wrapper| Ok(rotate(wrapper, degrees, settings?)))
     }
 
     /// Flip this image horizontally or vertically. Returns a new image
@@ -187,17 +865,90 @@ impl CommonImage {
     /// `horizontal`: whether to flip horizontally, otherwise it will flip vertically. default is `true`
     #[napi]
     pub fn flip(&self, horizontal: Option<bool>) -> Self {
-        Self {
-            wrapper: self.wrapper.flip(horizontal.unwrap_or(true))
-        }
+        Self::new(self.wrapper.flip(horizontal.unwrap_or(true)))
+    }
+
+    /// Async version of {@link flip}, the flip runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn flip_async(&self, horizontal: Option<bool>) -> AsyncTask<ImageTask<CommonImage>> {
+        let horizontal = horizontal.unwrap_or(true);
+        self.transform_async(move |wrapper| Ok(wrapper.flip(horizontal)))
+    }
+
+    /// Fill the whole image with a color. Returns a new image of the same size and color type, e.g. a background to paste onto
+    ///
+    /// ---
+    /// `color`: a CSS color such as `#rrggbbaa`, `rgb(255 0 0 / 50%)` or `white`, see {@link ImageLoader.blank}. Anything else throws an `ERR_INVALID_COLOR` error
+    #[napi]
+    pub fn fill(&self, color: String) -> Result<Self> {
+        Ok(Self::new(self.wrapper.fill(parse_color(&color)?)))
+    }
+
+    /// Async version of {@link fill}, the fill runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn fill_async(&self, color: String) -> AsyncTask<ImageTask<CommonImage>> {
+        let fill = parse_color(&color);
+        self.transform_async(move |wrapper| Ok(wrapper.fill(fill?)))
     }
 
     /// Crop this image. Returns a new image
+    ///
+    /// ---
+    /// The rectangle must be fully inside the image, otherwise an `ERR_CROP_OUT_OF_BOUNDS` error is thrown
     #[napi]
-    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Self {
-        Self {
-            wrapper: self.wrapper.crop(x, y, width, height)
-        }
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Result<Self> {
+        check_crop(&self.wrapper, x, y, width, height)?;
+        Ok(Self::new(self.wrapper.crop(x, y, width, height)))
+    }
+
+    /// Async version of {@link crop}, the crop runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn crop_async(&self, x: u32, y: u32, width: u32, height: u32) -> AsyncTask<ImageTask<CommonImage>> {
+        self.transform_async(move |wrapper| {
+            check_crop(wrapper, x, y, width, height)?;
+            Ok(wrapper.crop(x, y, width, height))
+        })
+    }
+
+    /// Convert the pixels from the embedded ICC profile to sRGB. Returns a new image without the profile
+    ///
+    /// ---
+    /// Call it before resizing and encoding, so that wide gamut sources (e.g. Display P3 or Adobe RGB photos) keep their colors in viewers that assume sRGB.
+    /// An image without a profile is returned as is. RGB matrix/TRC and gray TRC profiles are supported, others throw an `ERR_UNSUPPORTED` error
+    #[napi]
+    pub fn convert_to_srgb(&self) -> Result<Self> {
+        convert_to_srgb(&self.wrapper).map(Self::new)
+    }
+
+    /// Async version of {@link convertToSrgb}, the conversion runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn convert_to_srgb_async(&self) -> AsyncTask<ImageTask<CommonImage>> {
+        self.transform_async(convert_to_srgb)
+    }
+
+    /// Convert the pixels to another color type. Returns a new image, the metadata is kept
+    ///
+    /// ---
+    /// Colors are converted to gray by their luminance, and the alpha is dropped for color types without an alpha channel.
+    /// Samples are scaled between bit depths, e.g. `l8` for a grayscale thumbnail or `rgb8` before encoding a 16-bit PNG to JPEG. See {@link colorType} for the names
+    #[napi]
+    pub fn to_color_type(
+        &self,
+        #[napi(ts_arg_type = "'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'")]
+        color_type: String,
+    ) -> Result<Self> {
+        Ok(Self::new(self.wrapper.convert(parse_color_type(&color_type)?)))
+    }
+
+    /// Async version of {@link toColorType}, the conversion runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn to_color_type_async(
+        &self,
+        #[napi(ts_arg_type = "'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'")]
+        color_type: String,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        let color = parse_color_type(&color_type);
+        self.transform_async(move |wrapper| Ok(wrapper.convert(color?)))
     }
 
     // ========== ========== ========== ========== ==========
@@ -205,92 +956,268 @@ impl CommonImage {
     // ========== ========== ========== ========== ==========
 
     #[inline]
-    fn out(&self, format: ImageOutputFormat) -> Result<Vec<u8>> {
-        self.wrapper.buffer(format).map_err(|err| Error::from_reason(format!("{}", err)))
+    fn out(&self, format: ImageOutputFormat) -> Result<Buffer> {
+        encode(&self.wrapper, format).map(Buffer::from)
+    }
+
+    /// Encode on the libuv thread pool, sharing the source image with the task
+    #[inline]
+    fn out_async(&self, format: ImageOutputFormat) -> AsyncTask<ImageTask<Buffer>> {
+        let source = Arc::clone(&self.wrapper);
+        ImageTask::spawn(move || encode(&source, format).map(Buffer::from))
+    }
+
+    /// Get the pixel data as interleaved samples, row by row without padding, e.g. to feed a tensor or an `ImageData`
+    ///
+    /// ---
+    /// `colorType`: convert the pixels to this color type first, the color type of the image is used when absent.
+    /// 8-bit types return an `Uint8Array`, 16-bit types an `Uint16Array` and float types a `Float32Array`, see {@link ImageLoader.fromRaw} for the reverse
+    #[napi(ts_return_type = "Uint8Array | Uint16Array | Float32Array")]
+    pub fn to_raw(
+        &self,
+        #[napi(ts_arg_type = "'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'")]
+        color_type: Option<String>,
+    ) -> Result<Either3<Uint8Array, Uint16Array, Float32Array>> {
+        let converted = match color_type {
+            Some(name) => Cow::Owned(self.wrapper.convert(parse_color_type(&name)?)),
+            None => Cow::Borrowed(&*self.wrapper),
+        };
+
+        let color = converted.color();
+        let bytes = converted.as_bytes();
+        Ok(match color.bytes_per_pixel() / color.channel_count() {
+            1 => Either3::A(Uint8Array::new(bytes.to_vec())),
+            2 => Either3::B(Uint16Array::new(samples(bytes, u16::from_ne_bytes))),
+            _ => Either3::C(Float32Array::new(samples(bytes, f32::from_ne_bytes))),
+        })
+    }
+
+    /// Encode this image in the format picked at runtime, e.g. from an HTTP `Accept` header, and return the encoded bytes as a `Buffer`
+    ///
+    /// ---
+    /// `format`: a format name such as `png`, `jpeg`, `webp` or `pam`, or a MIME type such as `image/webp`. An unknown format throws an `ERR_UNSUPPORTED` error
+    ///
+    /// `options`: the options of the `to*` method of the format, an option that does not apply to it throws an `ERR_INVALID_PARAMETER` error. JPEG is encoded with quality `80` by default
+    #[napi]
+    pub fn encode(&self, format: String, options: Option<FormatOptions>) -> Result<Buffer> {
+        Target::parse(&format)?.encode(&self.wrapper, options).map(Buffer::from)
+    }
+
+    /// Async version of {@link encode}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn encode_async(&self, format: String, options: Option<FormatOptions>) -> AsyncTask<ImageTask<Buffer>> {
+        let source = Arc::clone(&self.wrapper);
+        ImageTask::spawn(move || Target::parse(&format)?.encode(&source, options).map(Buffer::from))
+    }
+
+    /// Encode this image in the format of the file extension and write it to `path`, without shuttling the bytes through JavaScript
+    ///
+    /// ---
+    /// `options`: see {@link encode}. An unknown extension throws an `ERR_UNSUPPORTED` error, a failed write an `ERR_IO` error
+    #[napi]
+    pub fn save(&self, path: String, options: Option<FormatOptions>) -> Result<()> {
+        save(&self.wrapper, &path, options)
+    }
+
+    /// Async version of {@link save}, the encoding and writing run on the libuv thread pool
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn save_async(&self, path: String, options: Option<FormatOptions>) -> AsyncTask<ImageTask<()>> {
+        let source = Arc::clone(&self.wrapper);
+        ImageTask::spawn(move || save(&source, &path, options))
     }
 
-    /// Encode this image as a PNG and return the encoded bytes
+    /// Encode this image as a PNG and return the encoded bytes as a `Buffer`
+    ///
+    /// ---
+    /// `options.compression` and `options.filter`: trade encoding time for size, an unknown value throws an `ERR_INVALID_PARAMETER` error
+    ///
+    /// `options.keepMetadata`: the EXIF, XMP and ICC color profile of the source can be embedded, IPTC has no place in PNG
     #[napi]
-    pub fn to_png(&self) -> Result<Vec<u8>> {
-        self.out(ImageOutputFormat::Png)
+    pub fn to_png(&self, options: Option<PngOptions>) -> Result<Buffer> {
+        encode_png(&self.wrapper, PngOptions::parse(options)?).map(Buffer::from)
+    }
+
+    /// Async version of {@link toPng}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_png_async(&self, options: Option<PngOptions>) -> AsyncTask<ImageTask<Buffer>> {
+        let source = Arc::clone(&self.wrapper);
+        let settings = PngOptions::parse(options);
+        ImageTask::spawn(move || encode_png(&source, settings?).map(Buffer::from))
     }
 
-    /// Encode this image as a JPEG(with specified quality) and return the encoded bytes
+    /// Encode this image as a WebP and return the encoded bytes as a `Buffer`
     ///
     /// ---
-    /// `quality`: Valid within `1-100` and should be a `u8`, otherwise it will cause a panic
+    /// `options.lossless`: only lossless encoding is available, `false` throws an `ERR_UNSUPPORTED` error. 16-bit and floating point images are stored as 8-bit
+    ///
+    /// `options.keepMetadata`: the EXIF, XMP and ICC color profile of the source can be embedded, IPTC has no place in WebP
     #[napi]
-    pub fn to_jpeg(&self, quality: u8) -> Result<Vec<u8>> {
-        self.out(ImageOutputFormat::Jpeg(quality))
+    pub fn to_webp(&self, options: Option<WebpOptions>) -> Result<Buffer> {
+        encode_webp(&self.wrapper, WebpOptions::keep(options)?).map(Buffer::from)
+    }
+
+    /// Async version of {@link toWebp}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_webp_async(&self, options: Option<WebpOptions>) -> AsyncTask<ImageTask<Buffer>> {
+        let source = Arc::clone(&self.wrapper);
+        let keep = WebpOptions::keep(options);
+        ImageTask::spawn(move || encode_webp(&source, keep?).map(Buffer::from))
+    }
+
+    /// Encode this image as an AVIF and return the encoded bytes as a `Buffer`
+    ///
+    /// ---
+    /// `options.quality`: Valid within `1-100`, otherwise an `ERR_INVALID_QUALITY` error is thrown. `options.speed` and `options.alpha` out of range throw an `ERR_INVALID_PARAMETER` error
+    ///
+    /// The encoder is only available when built with the `avif` feature, otherwise an `ERR_UNSUPPORTED` error is thrown
+    #[napi]
+    pub fn to_avif(&self, options: Option<AvifOptions>) -> Result<Buffer> {
+        encode_avif(&self.wrapper, AvifOptions::parse(options)?).map(Buffer::from)
+    }
+
+    /// Async version of {@link toAvif}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_avif_async(&self, options: Option<AvifOptions>) -> AsyncTask<ImageTask<Buffer>> {
+        let source = Arc::clone(&self.wrapper);
+        let settings = AvifOptions::parse(options);
+        ImageTask::spawn(move || encode_avif(&source, settings?).map(Buffer::from))
+    }
+
+    /// Encode this image as a JPEG(with specified quality) and return the encoded bytes as a `Buffer`
+    ///
+    /// ---
+    /// `quality`: Valid within `1-100`, otherwise an `ERR_INVALID_QUALITY` error is thrown
+    ///
+    /// `options.keepMetadata`: the EXIF, XMP, IPTC and ICC color profile of the source can be embedded
+    #[napi]
+    pub fn to_jpeg(&self, quality: u32, options: Option<EncodeOptions>) -> Result<Buffer> {
+        check_quality(quality)?;
+        encode_with(&self.wrapper, ImageOutputFormat::Jpeg(quality as u8), EncodeOptions::keep(options)?).map(Buffer::from)
+    }
+
+    /// Async version of {@link toJpeg}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_jpeg_async(&self, quality: u32, options: Option<EncodeOptions>) -> AsyncTask<ImageTask<Buffer>> {
+        let source = Arc::clone(&self.wrapper);
+        let keep = EncodeOptions::keep(options);
+        ImageTask::spawn(move || {
+            check_quality(quality)?;
+            encode_with(&source, ImageOutputFormat::Jpeg(quality as u8), keep?).map(Buffer::from)
+        })
     }
 
-    /// Encode this image as a PNM(in variant PBM) and return the encoded bytes
+    /// Encode this image as a PNM(in variant PBM) and return the encoded bytes as a `Buffer`
     ///
     /// ---
     /// `binary_sample`: whether to use binary sample encoding, otherwise it will use ascii sample encoding. default is `true` for smaller size
     #[napi]
-    pub fn to_pbm(&self, binary_sample: Option<bool>) -> Result<Vec<u8>> {
+    pub fn to_pbm(&self, binary_sample: Option<bool>) -> Result<Buffer> {
         let se = binary_sample.map_or(SampleEncoding::Binary, |b| {
             if b { SampleEncoding::Binary } else { SampleEncoding::Ascii }
         });
         self.out(ImageOutputFormat::Pnm(PnmSubtype::Bitmap(se)))
     }
 
-    /// Encode this image as a PNM(in variant PGM) and return the encoded bytes
+    /// Async version of {@link toPbm}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_pbm_async(&self, binary_sample: Option<bool>) -> AsyncTask<ImageTask<Buffer>> {
+        let se = binary_sample.map_or(SampleEncoding::Binary, |b| {
+            if b { SampleEncoding::Binary } else { SampleEncoding::Ascii }
+        });
+        self.out_async(ImageOutputFormat::Pnm(PnmSubtype::Bitmap(se)))
+    }
+
+    /// Encode this image as a PNM(in variant PGM) and return the encoded bytes as a `Buffer`
     ///
     /// ---
     /// `binary_sample`: whether to use binary sample encoding, otherwise it will use ascii sample encoding. default is `true` for smaller size
     #[napi]
-    pub fn to_pgm(&self, binary_sample: Option<bool>) -> Result<Vec<u8>> {
+    pub fn to_pgm(&self, binary_sample: Option<bool>) -> Result<Buffer> {
         let se = binary_sample.map_or(SampleEncoding::Binary, |b| {
             if b { SampleEncoding::Binary } else { SampleEncoding::Ascii }
         });
         self.out(ImageOutputFormat::Pnm(PnmSubtype::Graymap(se)))
     }
 
-    /// Encode this image as a PNM(in variant PPM) and return the encoded bytes
+    /// Async version of {@link toPgm}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_pgm_async(&self, binary_sample: Option<bool>) -> AsyncTask<ImageTask<Buffer>> {
+        let se = binary_sample.map_or(SampleEncoding::Binary, |b| {
+            if b { SampleEncoding::Binary } else { SampleEncoding::Ascii }
+        });
+        self.out_async(ImageOutputFormat::Pnm(PnmSubtype::Graymap(se)))
+    }
+
+    /// Encode this image as a PNM(in variant PPM) and return the encoded bytes as a `Buffer`
     ///
     /// ---
     /// `binary_sample`: whether to use binary sample encoding, otherwise it will use ascii sample encoding. default is `true` for smaller size
     #[napi]
-    pub fn to_ppm(&self, binary_sample: Option<bool>) -> Result<Vec<u8>> {
+    pub fn to_ppm(&self, binary_sample: Option<bool>) -> Result<Buffer> {
         let se = binary_sample.map_or(SampleEncoding::Binary, |b| {
             if b { SampleEncoding::Binary } else { SampleEncoding::Ascii }
         });
         self.out(ImageOutputFormat::Pnm(PnmSubtype::Pixmap(se)))
     }
 
-    /// Encode this image as a PNM(extended as PAM) and return the encoded bytes
+    /// Async version of {@link toPpm}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_ppm_async(&self, binary_sample: Option<bool>) -> AsyncTask<ImageTask<Buffer>> {
+        let se = binary_sample.map_or(SampleEncoding::Binary, |b| {
+            if b { SampleEncoding::Binary } else { SampleEncoding::Ascii }
+        });
+        self.out_async(ImageOutputFormat::Pnm(PnmSubtype::Pixmap(se)))
+    }
+
+    /// Encode this image as a PNM(extended as PAM) and return the encoded bytes as a `Buffer`
     #[napi]
-    pub fn to_pam(&self) -> Result<Vec<u8>> {
+    pub fn to_pam(&self) -> Result<Buffer> {
         self.out(ImageOutputFormat::Pnm(PnmSubtype::ArbitraryMap))
     }
 
-    /// Encode this image as a PNM with specified subtype and sample encoding(if any), and return the encoded bytes
+    /// Async version of {@link toPam}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_pam_async(&self) -> AsyncTask<ImageTask<Buffer>> {
+        self.out_async(ImageOutputFormat::Pnm(PnmSubtype::ArbitraryMap))
+    }
+
+    /// Encode this image as a PNM with specified subtype and sample encoding(if any), and return the encoded bytes as a `Buffer`
     ///
     /// ---
-    /// see `to_pbm`, `to_pgm`, `to_ppm`, `to_pam` for more details
-    pub fn to_pnm(&self, subtype: String, binary_sample: Option<bool>) -> Result<Vec<u8>> {
+    /// see `to_pbm`, `to_pgm`, `to_ppm`, `to_pam` for more details, an unknown subtype throws an `ERR_INVALID_PNM_SUBTYPE` error
+    #[napi]
+    pub fn to_pnm(
+        &self,
+        #[napi(ts_arg_type = "'pbm'|'pgm'|'ppm'|'pam'")]
+        subtype: String,
+        binary_sample: Option<bool>,
+    ) -> Result<Buffer> {
         match &*subtype {
             "pbm" => self.to_pbm(binary_sample),
             "pgm" => self.to_pgm(binary_sample),
             "ppm" => self.to_ppm(binary_sample),
             "pam" => self.to_pam(),
-            _ => Err(Error::from_reason(format!("unknown pnm subtype: {}", subtype)))
+            _ => Err(error(ErrorCode::InvalidPnmSubtype, "Invalid pnm subtype", subtype))
         }
     }
 
-    /// Encode this image as a GIF and return the encoded bytes
+    /// Encode this image as a GIF and return the encoded bytes as a `Buffer`
     #[napi]
-    pub fn to_gif(&self) -> Result<Vec<u8>> {
+    pub fn to_gif(&self) -> Result<Buffer> {
         self.out(ImageOutputFormat::Gif)
     }
 
-    /// Encode this image as a ICO and return the encoded bytes
+    /// Async version of {@link toGif}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_gif_async(&self) -> AsyncTask<ImageTask<Buffer>> {
+        self.out_async(ImageOutputFormat::Gif)
+    }
+
+    /// Encode this image as a ICO and return the encoded bytes as a `Buffer`
     ///
     /// ---
     /// `strategy`: The strategy used when the width or height of the image exceeds 256. Its value is in the format of '&lt;mode&gt;_&lt;filter&gt;'.
-    /// - `undefined`: No conversion is used, will cause a panic if the width or height of the image exceeds 256
+    /// - `undefined`: No conversion is used, an `ERR_TOO_LARGE` error is thrown if the width or height of the image exceeds 256
     ///
     /// 'mode' can be one of the following:
     /// - `fit`: The image's aspect ratio is preserved. The image is scaled to the maximum possible size that fits within **256x256**.
@@ -308,73 +1235,128 @@ impl CommonImage {
         &self,
         #[napi(ts_arg_type = "'fit_nearest'|'fit_triangle'|'fit_catmullRom'|'fit_gaussian'|'fit_lanczos3'|'cover_nearest'|'cover_triangle'|'cover_catmullRom'|'cover_gaussian'|'cover_lanczos3'|'exact_nearest'|'exact_triangle'|'exact_catmullRom'|'exact_gaussian'|'exact_lanczos3'")]
         strategy: Option<String>,
-    ) -> Result<Vec<u8>> {
-        let (w, h) = self.wrapper.dimensions();
+    ) -> Result<Buffer> {
+        encode_ico(&self.wrapper, strategy).map(Buffer::from)
+    }
 
-        if w <= 256 && h <= 256 {
-            self.out(ImageOutputFormat::Ico)
-        } else {
-            match strategy {
-                Some(inner) => match strategy_parser(&inner) {
-                    Ok((mode, filter)) => {
-                        let transferred = match mode {
-                            "fit" => self.wrapper.resize_to_fit(256, 256, filter),
-                            "cover" => self.wrapper.resize_to_cover(256, 256, filter),
-                            "exact" => self.wrapper.resize_exact(256, 256, filter),
-                            _ => return Err(Error::from_reason(format!("This should never happen, please report this issue to me!"))),
-                        };
-
-                        // transferred.out(ImageOutputFormat::Ico)
-                        transferred
-                            .buffer(ImageOutputFormat::Ico)
-                            .map_err(|err| Error::from_reason(format!("{}", err)))
-                    }
-                    Err(err_msg) => Err(Error::from_reason(err_msg))
-                },
-                None => Err(Error::from_reason(format!("image size is too large for ico format, max size is 256x256"))),
-            }
-        }
+    /// Async version of {@link toIco}, the conversion and encoding run on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_ico_async(
+        &self,
+        #[napi(ts_arg_type = "'fit_nearest'|'fit_triangle'|'fit_catmullRom'|'fit_gaussian'|'fit_lanczos3'|'cover_nearest'|'cover_triangle'|'cover_catmullRom'|'cover_gaussian'|'cover_lanczos3'|'exact_nearest'|'exact_triangle'|'exact_catmullRom'|'exact_gaussian'|'exact_lanczos3'")]
+        strategy: Option<String>,
+    ) -> AsyncTask<ImageTask<Buffer>> {
+        let source = Arc::clone(&self.wrapper);
+        ImageTask::spawn(move || encode_ico(&source, strategy).map(Buffer::from))
     }
 
-    /// Encode this image as a BMP and return the encoded bytes
+    /// Encode this image as a BMP and return the encoded bytes as a `Buffer`
     #[napi]
-    pub fn to_bmp(&self) -> Result<Vec<u8>> {
+    pub fn to_bmp(&self) -> Result<Buffer> {
         self.out(ImageOutputFormat::Bmp)
     }
 
-    /// Encode this image as a Farbfeld and return the encoded bytes
+    /// Async version of {@link toBmp}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_bmp_async(&self) -> AsyncTask<ImageTask<Buffer>> {
+        self.out_async(ImageOutputFormat::Bmp)
+    }
+
+    /// Encode this image as a Farbfeld and return the encoded bytes as a `Buffer`
     #[napi]
-    pub fn to_farbfeld(&self) -> Result<Vec<u8>> {
+    pub fn to_farbfeld(&self) -> Result<Buffer> {
         self.out(ImageOutputFormat::Farbfeld)
     }
 
-    /// Encode this image as a TGA and return the encoded bytes
+    /// Async version of {@link toFarbfeld}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_farbfeld_async(&self) -> AsyncTask<ImageTask<Buffer>> {
+        self.out_async(ImageOutputFormat::Farbfeld)
+    }
+
+    /// Encode this image as a TGA and return the encoded bytes as a `Buffer`
     #[napi]
-    pub fn to_tga(&self) -> Result<Vec<u8>> {
+    pub fn to_tga(&self) -> Result<Buffer> {
         self.out(ImageOutputFormat::Tga)
     }
 
-    /// Encode this image as a OpenExr and return the encoded bytes
+    /// Async version of {@link toTga}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_tga_async(&self) -> AsyncTask<ImageTask<Buffer>> {
+        self.out_async(ImageOutputFormat::Tga)
+    }
+
+    /// Encode this image as a OpenExr and return the encoded bytes as a `Buffer`
+    ///
+    /// ---
+    /// Integer samples are promoted to 32-bit floating point, within `0-1`
     #[napi(js_name = "toOpenExr")]
-    pub fn to_openexr(&self) -> Result<Vec<u8>> {
+    pub fn to_openexr(&self) -> Result<Buffer> {
         self.out(ImageOutputFormat::OpenExr)
     }
 
-    /// Encode this image as a TIFF and return the encoded bytes
+    /// Async version of {@link toOpenExr}, the encoding runs on the libuv thread pool
+    #[napi(js_name = "toOpenExrAsync", ts_return_type = "Promise<Buffer>")]
+    pub fn to_openexr_async(&self) -> AsyncTask<ImageTask<Buffer>> {
+        self.out_async(ImageOutputFormat::OpenExr)
+    }
+
+    /// Encode this image as a Radiance HDR and return the encoded bytes as a `Buffer`
+    ///
+    /// ---
+    /// The samples are converted to 32-bit floating point RGB, the alpha channel is dropped
+    #[napi]
+    pub fn to_hdr(&self) -> Result<Buffer> {
+        self.wrapper.hdr().map(Buffer::from).map_err(image_error)
+    }
+
+    /// Async version of {@link toHdr}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_hdr_async(&self) -> AsyncTask<ImageTask<Buffer>> {
+        let source = Arc::clone(&self.wrapper);
+        ImageTask::spawn(move || source.hdr().map(Buffer::from).map_err(image_error))
+    }
+
+    /// Encode this image as a TIFF and return the encoded bytes as a `Buffer`
     #[napi]
-    pub fn to_tiff(&self) -> Result<Vec<u8>> {
+    pub fn to_tiff(&self) -> Result<Buffer> {
         self.out(ImageOutputFormat::Tiff)
     }
 
-    /// Encode this image as a QOI and return the encoded bytes
+    /// Async version of {@link toTiff}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_tiff_async(&self) -> AsyncTask<ImageTask<Buffer>> {
+        self.out_async(ImageOutputFormat::Tiff)
+    }
+
+    /// Encode this image as a QOI and return the encoded bytes as a `Buffer`
     #[napi]
-    pub fn to_qoi(&self) -> Result<Vec<u8>> {
+    pub fn to_qoi(&self) -> Result<Buffer> {
         self.out(ImageOutputFormat::Qoi)
     }
+
+    /// Async version of {@link toQoi}, the encoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<Buffer>")]
+    pub fn to_qoi_async(&self) -> AsyncTask<ImageTask<Buffer>> {
+        self.out_async(ImageOutputFormat::Qoi)
+    }
+}
+
+/// The `Array<number>` output the encoders returned before `Buffer`, only built with the `bench` feature to compare against
+#[cfg(feature = "bench")]
+#[napi]
+impl CommonImage {
+    /// {@link toBmp} with the bytes marshalled one by one to an `Array<number>`
+    #[napi]
+    pub fn to_bmp_numbers(&self) -> Result<Vec<u8>> {
+        encode(&self.wrapper, ImageOutputFormat::Bmp)
+    }
 }
 
 #[cfg(test)]
 mod unit_test {
+    use image::GenericImageView;
+    use crate::metadata::Metadata;
     use super::*;
 
     #[test]
@@ -382,4 +1364,342 @@ mod unit_test {
         let res = strategy_parser("fit_nearest");
         println!("{:?}", res);
     }
+
+    fn image(w: u32, h: u32) -> CommonImage {
+        CommonImage::new(ImageWrapper::new(image::DynamicImage::new_rgb8(w, h)))
+    }
+
+    fn code<T>(res: Result<T>) -> ErrorCode {
+        match res {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => err.status,
+        }
+    }
+
+    #[test]
+    fn convert_to_srgb() {
+        let img = image(4, 4);
+        assert!(img.wrapper.metadata().icc.is_none());
+        assert_eq!(img.convert_to_srgb().unwrap().dimensions(), vec![4, 4]);
+
+        let lut = ImageWrapper::new(image::DynamicImage::new_rgb8(4, 4)).with_metadata(Metadata {
+            icc: Some(vec![0; 132]),
+            ..Default::default()
+        });
+        assert_eq!(code(CommonImage::new(lut).convert_to_srgb()), ErrorCode::Unsupported);
+    }
+
+    fn png(compression: &str, filter: &str) -> Result<Vec<u8>> {
+        let source = concat!(env!("CARGO_MANIFEST_DIR"), "/__test__/_source/tree.png");
+        let wrapper = ImageWrapper::new(image::open(source).unwrap());
+        let options = PngOptions { compression: Some(compression.into()), filter: Some(filter.into()), ..Default::default() };
+        encode_png(&wrapper, PngOptions::parse(Some(options))?)
+    }
+
+    #[test]
+    fn png_compression() {
+        let fast = png("fast", "adaptive").unwrap();
+        let best = png("best", "adaptive").unwrap();
+        assert!(best.len() < fast.len(), "best {} fast {}", best.len(), fast.len());
+
+        for filter in ["none", "sub", "up", "avg", "paeth"] {
+            let encoded = png("default", filter).unwrap();
+            assert_eq!(image::load_from_memory(&encoded).unwrap().to_rgba8(), image::load_from_memory(&best).unwrap().to_rgba8());
+        }
+
+        assert_eq!(code(png("max", "adaptive")), ErrorCode::InvalidParameter);
+        assert_eq!(code(png("best", "average")), ErrorCode::InvalidParameter);
+    }
+
+    #[test]
+    fn webp() {
+        let source = concat!(env!("CARGO_MANIFEST_DIR"), "/__test__/_source/tree.png");
+        let decoded = image::open(source).unwrap();
+
+        // lossless for every color type, the wider ones are stored as 8-bit
+        for image in [decoded.clone(), decoded.to_luma8().into(), decoded.to_rgba16().into(), decoded.to_rgb32f().into()] {
+            let encoded = encode_webp(&ImageWrapper::new(image.clone()), Keep::default()).unwrap();
+            let roundtrip = image::load_from_memory_with_format(&encoded, ImageFormat::WebP).unwrap();
+            assert_eq!(roundtrip.to_rgba8(), image.to_rgba8());
+        }
+
+        let lossy = WebpOptions { lossless: Some(false), ..Default::default() };
+        assert_eq!(code(WebpOptions::keep(Some(lossy))), ErrorCode::Unsupported);
+    }
+
+    #[test]
+    fn avif() {
+        let options = |quality, speed, alpha| AvifOptions::parse(Some(AvifOptions { quality, speed, alpha }));
+        assert_eq!(code(options(Some(0), None, None)), ErrorCode::InvalidQuality);
+        assert_eq!(code(options(None, Some(11), None)), ErrorCode::InvalidParameter);
+        assert_eq!(code(options(None, None, Some(101))), ErrorCode::InvalidParameter);
+
+        let wrapper = ImageWrapper::new(image::DynamicImage::new_rgba8(16, 16));
+        let encoded = encode_avif(&wrapper, options(None, Some(10), None).unwrap());
+        if cfg!(feature = "avif") {
+            // the file type box of an AVIF
+            assert_eq!(&encoded.unwrap()[4..12], b"ftypavif");
+        } else {
+            assert_eq!(code(encoded), ErrorCode::Unsupported);
+        }
+
+        // round trip, a gradient with a transparent half
+        let image = image::RgbaImage::from_fn(32, 24, |x, y| Rgba([x as u8 * 8, y as u8 * 10, 128, if x < 16 { 255 } else { 0 }]));
+        let wrapper = ImageWrapper::new(image::DynamicImage::ImageRgba8(image.clone()));
+        let encoded = encode_avif(&wrapper, options(Some(100), Some(10), Some(100)).unwrap());
+        let load = |max_alloc| {
+            let mut limits = image::io::Limits::default();
+            limits.max_alloc = Some(max_alloc);
+            ImageWrapper::load(encoded.as_deref().unwrap_or_default(), ImageFormat::Avif, limits).map_err(image_error)
+        };
+        let decoded = load(512 * 1024 * 1024);
+        if cfg!(feature = "avif") {
+            // the 16-bit output and the planes of the decoder
+            assert!(load(32 * 24 * 4 * 2 * 2).is_ok());
+            assert_eq!(code(load(32 * 24 * 4 * 2 * 2 - 1)), ErrorCode::LimitExceeded);
+
+            let decoded = decoded.unwrap();
+            assert_eq!(decoded.dimensions(), (32, 24));
+            // ravif encodes with 10 bits
+            assert_eq!(decoded.color(), ColorType::Rgba16);
+            let decoded = decoded.to_rgba8();
+            for (a, b) in image.pixels().zip(decoded.pixels()) {
+                // the color of fully transparent pixels is free to change
+                assert_eq!(a[3], b[3]);
+                if a[3] == 255 {
+                    assert!(a.0.iter().zip(b.0.iter()).all(|(a, b)| a.abs_diff(*b) <= 8), "{:?} {:?}", a, b);
+                }
+            }
+        } else {
+            assert_eq!(code(decoded), ErrorCode::Unsupported);
+        }
+    }
+
+    #[test]
+    fn load_save_pairs() {
+        let source = concat!(env!("CARGO_MANIFEST_DIR"), "/__test__/_source");
+        let outputs = [
+            (ImageFormat::Png, ImageOutputFormat::Png),
+            (ImageFormat::Jpeg, ImageOutputFormat::Jpeg(80)),
+            (ImageFormat::Pnm, ImageOutputFormat::Pnm(PnmSubtype::Bitmap(SampleEncoding::Binary))),
+            (ImageFormat::Pnm, ImageOutputFormat::Pnm(PnmSubtype::Bitmap(SampleEncoding::Ascii))),
+            (ImageFormat::Pnm, ImageOutputFormat::Pnm(PnmSubtype::Graymap(SampleEncoding::Binary))),
+            (ImageFormat::Pnm, ImageOutputFormat::Pnm(PnmSubtype::Pixmap(SampleEncoding::Ascii))),
+            (ImageFormat::Pnm, ImageOutputFormat::Pnm(PnmSubtype::ArbitraryMap)),
+            (ImageFormat::Gif, ImageOutputFormat::Gif),
+            (ImageFormat::Ico, ImageOutputFormat::Ico),
+            (ImageFormat::Bmp, ImageOutputFormat::Bmp),
+            (ImageFormat::Farbfeld, ImageOutputFormat::Farbfeld),
+            (ImageFormat::Tga, ImageOutputFormat::Tga),
+            (ImageFormat::OpenExr, ImageOutputFormat::OpenExr),
+            (ImageFormat::Tiff, ImageOutputFormat::Tiff),
+            (ImageFormat::Qoi, ImageOutputFormat::Qoi),
+        ];
+
+        let mut loaded = 0;
+        for entry in std::fs::read_dir(source).unwrap() {
+            let path = entry.unwrap().path();
+            let Ok(format) = ImageFormat::from_path(&path) else { continue };
+            // avif and dds have no decoder in this build
+            let Ok(decoded) = image::open(&path) else { continue };
+            // a corner keeps the color type and is quick to encode in every format
+            let wrapper = ImageWrapper::new(decoded.crop_imm(0, 0, 33, 17));
+            let dimensions = wrapper.dimensions();
+            loaded += 1;
+
+            let mut encoded: Vec<(ImageFormat, Vec<u8>)> = outputs.iter()
+                .map(|(target, output)| (*target, encode(&wrapper, output.clone())))
+                .map(|(target, res)| (target, res.unwrap_or_else(|err| panic!("{:?} to {:?}: {}", format, target, err.reason))))
+                .collect();
+            encoded.push((ImageFormat::WebP, encode_webp(&wrapper, Keep::default()).unwrap()));
+            encoded.push((ImageFormat::Hdr, wrapper.hdr().unwrap()));
+
+            for (target, bytes) in encoded {
+                // the pnm decoder does not read the alpha tuple types of pam, which the encoder writes
+                if target == ImageFormat::Pnm && bytes.windows(6).any(|w| w == b"_ALPHA") {
+                    continue;
+                }
+                let roundtrip = image::load_from_memory_with_format(&bytes, target)
+                    .unwrap_or_else(|err| panic!("{:?} to {:?}: {}", format, target, err));
+                assert_eq!(roundtrip.dimensions(), dimensions, "{:?} to {:?}", format, target);
+            }
+        }
+        assert!(loaded >= 15, "{} fixtures loaded", loaded);
+    }
+
+    #[test]
+    fn target_parse() {
+        assert_eq!(Target::parse("png").unwrap(), Target::Png);
+        assert_eq!(Target::parse("image/JPEG").unwrap(), Target::Jpeg);
+        assert_eq!(Target::parse(" image/webp;q=0.9 ").unwrap(), Target::WebP);
+        assert_eq!(Target::parse("image/x-portable-arbitrarymap").unwrap(), Target::Pam);
+        assert_eq!(Target::parse("image/vnd.microsoft.icon").unwrap(), Target::Ico);
+        assert_eq!(code(Target::parse("image/heic")), ErrorCode::Unsupported);
+        assert_eq!(code(Target::parse("*/*")), ErrorCode::Unsupported);
+    }
+
+    #[test]
+    fn target_encode() {
+        let wrapper = ImageWrapper::new(image::DynamicImage::new_rgb8(8, 8));
+        for (format, expected) in [("image/png", ImageFormat::Png), ("jpg", ImageFormat::Jpeg), ("webp", ImageFormat::WebP), ("exr", ImageFormat::OpenExr), ("pgm", ImageFormat::Pnm)] {
+            let encoded = Target::parse(format).unwrap().encode(&wrapper, None).unwrap();
+            assert_eq!(image::guess_format(&encoded).unwrap(), expected, "{}", format);
+        }
+
+        let ascii = Target::Pbm.encode(&wrapper, Some(FormatOptions { binary_sample: Some(false), ..Default::default() })).unwrap();
+        assert!(ascii.starts_with(b"P1"));
+        let best = Target::Png.encode(&wrapper, Some(FormatOptions { compression: Some("best".into()), ..Default::default() }));
+        assert!(best.is_ok());
+
+        // options of another format, or out of range
+        assert_eq!(code(Target::Png.encode(&wrapper, Some(FormatOptions { quality: Some(80), ..Default::default() }))), ErrorCode::InvalidParameter);
+        assert_eq!(code(Target::Gif.encode(&wrapper, Some(FormatOptions { strip_gps: Some(true), ..Default::default() }))), ErrorCode::InvalidParameter);
+        assert_eq!(code(Target::Jpeg.encode(&wrapper, Some(FormatOptions { quality: Some(101), ..Default::default() }))), ErrorCode::InvalidQuality);
+    }
+
+    #[test]
+    fn save_path() {
+        let wrapper = ImageWrapper::new(image::DynamicImage::new_rgb8(8, 8));
+        let dir = std::env::temp_dir();
+        let path = dir.join("fastigiata_image_save.webp");
+        let path = path.to_str().unwrap();
+
+        save(&wrapper, path, None).unwrap();
+        assert_eq!(image::open(path).unwrap().dimensions(), (8, 8));
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(code(save(&wrapper, dir.join("no_extension").to_str().unwrap(), None)), ErrorCode::Unsupported);
+        assert_eq!(code(save(&wrapper, dir.join("missing/dir/out.png").to_str().unwrap(), None)), ErrorCode::Io);
+    }
+
+    #[test]
+    fn invalid_filter() {
+        let img = image(4, 4);
+        assert_eq!(code(img.resize_to_fit(2, 2, Some("lanczos4".into()))), ErrorCode::InvalidFilter);
+        assert_eq!(code(img.resize_to_cover(2, 2, Some("".into()))), ErrorCode::InvalidFilter);
+        assert_eq!(code(img.resize_exact(2, 2, Some("Nearest".into()))), ErrorCode::InvalidFilter);
+        assert!(img.resize_exact(2, 2, Some("catmullRom".into())).is_ok());
+    }
+
+    #[test]
+    fn invalid_strategy() {
+        let img = ImageWrapper::new(image::DynamicImage::new_rgb8(300, 300));
+        assert_eq!(code(encode_ico(&img, Some("fit".into()))), ErrorCode::InvalidStrategy);
+        assert_eq!(code(encode_ico(&img, Some("fill_nearest".into()))), ErrorCode::InvalidStrategy);
+        assert_eq!(code(encode_ico(&img, Some("fit_lanczos4".into()))), ErrorCode::InvalidStrategy);
+        assert_eq!(code(encode_ico(&img, None)), ErrorCode::TooLarge);
+        assert!(encode_ico(&img, Some("cover_triangle".into())).is_ok());
+    }
+
+    #[test]
+    fn zero_size() {
+        let img = image(4, 4);
+        assert_eq!(code(img.resize_to_fit(0, 2, None)), ErrorCode::ZeroSize);
+        assert_eq!(code(img.resize_to_cover(2, 0, None)), ErrorCode::ZeroSize);
+        assert_eq!(code(img.resize_exact(0, 0, None)), ErrorCode::ZeroSize);
+        assert_eq!(code(img.crop(0, 0, 0, 2)), ErrorCode::ZeroSize);
+    }
+
+    #[test]
+    fn crop_out_of_bounds() {
+        let img = image(4, 4);
+        assert_eq!(code(img.crop(1, 0, 4, 4)), ErrorCode::CropOutOfBounds);
+        assert_eq!(code(img.crop(0, 4, 1, 1)), ErrorCode::CropOutOfBounds);
+        assert_eq!(code(img.crop(u32::MAX, 0, 2, 2)), ErrorCode::CropOutOfBounds);
+        assert!(img.crop(2, 2, 2, 2).is_ok());
+    }
+
+    #[test]
+    fn invalid_quality() {
+        assert_eq!(code(check_quality(0)), ErrorCode::InvalidQuality);
+        assert_eq!(code(check_quality(101)), ErrorCode::InvalidQuality);
+        assert!(check_quality(1).is_ok());
+        assert!(check_quality(100).is_ok());
+    }
+
+    #[test]
+    fn invalid_quarter() {
+        let img = image(4, 4);
+        assert_eq!(code(img.rotate_quarter(4)), ErrorCode::InvalidQuarter);
+        assert!(img.rotate_quarter(3).is_ok());
+    }
+
+    #[test]
+    fn message_format() {
+        let err = image(4, 4).resize_to_fit(2, 2, Some("lanczos4".into())).err().unwrap();
+        assert_eq!(err.status.as_ref(), "ERR_INVALID_FILTER");
+        assert_eq!(err.reason, "Invalid filter | lanczos4");
+    }
+
+    #[test]
+    fn pixels() {
+        let mut img = image(2, 2);
+        let shared = img.shared();
+        img.put_pixel(0, 1, vec![10, 20, 30, 255]).unwrap();
+        assert_eq!(img.get_pixel(0, 1).unwrap(), vec![10, 20, 30, 255]);
+        assert_eq!(shared.pixel(0, 1), Some(Rgba([0, 0, 0, 255])));
+
+        assert_eq!(code(img.get_pixel(2, 0)), ErrorCode::PixelOutOfBounds);
+        assert_eq!(code(img.put_pixel(0, 2, vec![0, 0, 0, 0])), ErrorCode::PixelOutOfBounds);
+        assert_eq!(code(img.put_pixel(0, 0, vec![256, 0, 0, 0])), ErrorCode::InvalidParameter);
+        assert_eq!(code(img.put_pixel(0, 0, vec![0, 0, 0])), ErrorCode::InvalidParameter);
+    }
+
+    #[test]
+    fn fill() {
+        let img = image(2, 2).fill("rgba(255, 0, 0, 50%)".into()).unwrap();
+        assert_eq!(img.color_type(), "rgb8");
+        assert_eq!(img.get_pixel(1, 1).unwrap(), vec![255, 0, 0, 255]);
+        assert_eq!(code(img.fill("reddish".into())), ErrorCode::InvalidColor);
+    }
+
+    #[test]
+    fn to_color_type() {
+        let mut img = image(2, 1);
+        img.put_pixel(1, 0, vec![255, 0, 0, 255]).unwrap();
+
+        let gray = img.to_color_type("l8".into()).unwrap();
+        assert_eq!(gray.color_type(), "l8");
+        assert_eq!(gray.get_pixel(1, 0).unwrap(), vec![54, 54, 54, 255]);
+
+        let deep = img.to_color_type("rgba16".into()).unwrap();
+        assert_eq!((deep.color_type(), deep.bpp()), ("rgba16".to_string(), 64));
+        assert_eq!(deep.get_pixel(1, 0).unwrap(), vec![255, 0, 0, 255]);
+        assert_eq!(code(img.to_color_type("cmyk8".into())), ErrorCode::InvalidParameter);
+    }
+
+    #[test]
+    fn rotate() {
+        let img = image(10, 6);
+        let rotated = img.rotate(-30.0, None).unwrap();
+        assert_eq!((rotated.dimensions(), rotated.color_type()), (vec![10, 6], "rgba8".to_string()));
+
+        let options = |expand, background: &str, interpolation: &str| Some(RotateOptions {
+            expand: Some(expand),
+            background: Some(background.into()),
+            interpolation: Some(interpolation.into()),
+        });
+        let expanded = img.rotate(45.0, options(true, "white", "bicubic")).unwrap();
+        assert_eq!((expanded.dimensions(), expanded.color_type()), (vec![12, 12], "rgb8".to_string()));
+        assert_eq!(expanded.get_pixel(0, 0).unwrap(), vec![255, 255, 255, 255]);
+
+        assert_eq!(code(img.rotate(f64::NAN, None)), ErrorCode::InvalidParameter);
+        assert_eq!(code(img.rotate(10.0, options(false, "white", "lanczos3"))), ErrorCode::InvalidParameter);
+        assert_eq!(code(img.rotate(10.0, options(false, "whitish", "nearest"))), ErrorCode::InvalidColor);
+    }
+
+    #[test]
+    fn tiff_metadata() {
+        let source = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/__test__/_source/tree.tiff")).unwrap();
+        let wrapper = ImageWrapper::load(&source, ImageFormat::Tiff, Default::default()).unwrap()
+            .with_metadata(Metadata::read(&source, ImageFormat::Tiff));
+        let all = || Some(FormatOptions { keep_metadata: Some(Either::A("all".into())), ..Default::default() });
+
+        // the kept EXIF holds the tags of the TIFF, not its pixel data
+        for target in [Target::parse("png").unwrap(), Target::parse("webp").unwrap(), Target::parse("jpeg").unwrap()] {
+            let plain = target.encode(&wrapper, None).unwrap();
+            let kept = target.encode(&wrapper, all()).unwrap();
+            assert!(kept.len() < plain.len() + 4096, "{} bytes, {} without metadata", kept.len(), plain.len());
+        }
+    }
 }
\ No newline at end of file
@@ -1,26 +1,174 @@
+use std::borrow::Cow;
 use std::io::Cursor;
-use image::{ColorType, DynamicImage, GenericImageView, ImageError, ImageFormat, ImageOutputFormat};
+use std::sync::Arc;
+use image::{ColorType, DynamicImage, GenericImage, GenericImageView, ImageBuffer, ImageEncoder, ImageError, ImageFormat, ImageOutputFormat, Rgba, Rgba32FImage, RgbaImage};
+use image::codecs::hdr::HdrEncoder;
+use image::codecs::png::{CompressionType, FilterType as PngFilterType, PngEncoder};
+use image::codecs::pnm::{PnmSubtype, SampleEncoding};
+use image::codecs::webp::WebPEncoder;
 use image::imageops::FilterType;
+use image::io::{Limits, Reader};
+use crate::color::Profile;
+use crate::metadata::Metadata;
 
+/// Split native-endian bytes into samples, e.g. `samples(bytes, u16::from_ne_bytes)`
+pub fn samples<T, const N: usize>(data: &[u8], from: fn([u8; N]) -> T) -> Vec<T> {
+    data.chunks_exact(N).map(|c| from(c.try_into().unwrap())).collect()
+}
+
+/// The sampling of an arbitrary-angle rotation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    Nearest,
+    Bilinear,
+    Bicubic,
+}
+
+impl Interpolation {
+    /// Get the first tap and the weights of the taps around a sample position, in pixel centers
+    fn taps(self, pos: f64) -> (i64, [f32; 4], usize) {
+        // the Catmull-Rom spline, as used by most image editors for bicubic sampling
+        fn cubic(x: f32) -> f32 {
+            let x = x.abs();
+            if x < 1.0 {
+                1.5 * x * x * x - 2.5 * x * x + 1.0
+            } else if x < 2.0 {
+                -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+            } else {
+                0.0
+            }
+        }
+
+        let base = pos.floor();
+        let t = (pos - base) as f32;
+        match self {
+            Interpolation::Nearest => (pos.round() as i64, [1.0, 0.0, 0.0, 0.0], 1),
+            Interpolation::Bilinear => (base as i64, [1.0 - t, t, 0.0, 0.0], 2),
+            Interpolation::Bicubic => (base as i64 - 1, [cubic(t + 1.0), cubic(t), cubic(1.0 - t), cubic(2.0 - t)], 4),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct ImageWrapper {
     /// a wrapper around ImageBuffer that provides dynamic behavior
     dyn_image: DynamicImage,
+    /// the metadata of the source image, shared by the images derived from it
+    metadata: Arc<Metadata>,
 }
 
 impl ImageWrapper {
     /// Create a new ImageWrapper from a DynamicImage
     pub fn new(inner: DynamicImage) -> Self {
         Self {
-            dyn_image: inner
+            dyn_image: inner,
+            metadata: Arc::default(),
+        }
+    }
+
+    /// Create a new ImageWrapper from a DynamicImage, keeping the metadata of this image
+    fn derive(&self, inner: DynamicImage) -> Self {
+        Self {
+            dyn_image: inner,
+            metadata: Arc::clone(&self.metadata),
         }
     }
 
-    /// Create a new ImageWrapper from image buffer with specified or auto-guess format.
-    pub fn load(buffer: Vec<u8>, format: Option<ImageFormat>) -> Result<Self, ImageError> {
-        match format {
-            None => image::load_from_memory(&buffer),
-            Some(f) => image::load_from_memory_with_format(&buffer, f)
-        }.map(|img| Self::new(img))
+    /// Attach the metadata captured from the source container
+    pub fn with_metadata(self, metadata: Metadata) -> Self {
+        Self {
+            dyn_image: self.dyn_image,
+            metadata: Arc::new(metadata),
+        }
+    }
+
+    /// Get the metadata captured from the source container
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Create a new ImageWrapper from image buffer with specified format, the decoder is restricted by the given limits.
+    pub fn load(buffer: &[u8], format: ImageFormat, limits: Limits) -> Result<Self, ImageError> {
+        #[cfg(feature = "avif")]
+        if format == ImageFormat::Avif {
+            return crate::avif::decode(buffer, limits).map(Self::new);
+        }
+        let mut reader = Reader::with_format(Cursor::new(buffer), format);
+        reader.limits(limits);
+        reader.decode().map(Self::new)
+    }
+
+    /// Create a new ImageWrapper from raw pixel data in the given color type, 16-bit and float samples are in native byte order.
+    /// Returns `None` if the length of the data does not match the dimensions
+    pub fn from_raw(data: &[u8], width: u32, height: u32, color: ColorType) -> Option<Self> {
+        let expected = width as u64 * height as u64 * color.bytes_per_pixel() as u64;
+        if data.len() as u64 != expected {
+            return None;
+        }
+
+        let image = match color {
+            ColorType::L8 => DynamicImage::ImageLuma8(ImageBuffer::from_raw(width, height, data.to_vec())?),
+            ColorType::La8 => DynamicImage::ImageLumaA8(ImageBuffer::from_raw(width, height, data.to_vec())?),
+            ColorType::Rgb8 => DynamicImage::ImageRgb8(ImageBuffer::from_raw(width, height, data.to_vec())?),
+            ColorType::Rgba8 => DynamicImage::ImageRgba8(ImageBuffer::from_raw(width, height, data.to_vec())?),
+            ColorType::L16 => DynamicImage::ImageLuma16(ImageBuffer::from_raw(width, height, samples(data, u16::from_ne_bytes))?),
+            ColorType::La16 => DynamicImage::ImageLumaA16(ImageBuffer::from_raw(width, height, samples(data, u16::from_ne_bytes))?),
+            ColorType::Rgb16 => DynamicImage::ImageRgb16(ImageBuffer::from_raw(width, height, samples(data, u16::from_ne_bytes))?),
+            ColorType::Rgba16 => DynamicImage::ImageRgba16(ImageBuffer::from_raw(width, height, samples(data, u16::from_ne_bytes))?),
+            ColorType::Rgb32F => DynamicImage::ImageRgb32F(ImageBuffer::from_raw(width, height, samples(data, f32::from_ne_bytes))?),
+            ColorType::Rgba32F => DynamicImage::ImageRgba32F(ImageBuffer::from_raw(width, height, samples(data, f32::from_ne_bytes))?),
+            _ => return None,
+        };
+        Some(Self::new(image))
+    }
+
+    /// Create a new ImageWrapper of the given color type, filled with an 8-bit RGBA color
+    pub fn blank(width: u32, height: u32, color: ColorType, fill: Rgba<u8>) -> Self {
+        Self::new(DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, fill))).convert(color)
+    }
+
+    /// Fill the whole image with an 8-bit RGBA color, the color type and the metadata are kept
+    pub fn fill(&self, fill: Rgba<u8>) -> Self {
+        let (width, height) = self.dimensions();
+        self.derive(Self::blank(width, height, self.color(), fill).dyn_image)
+    }
+
+    /// Get the pixel data, interleaved samples in native byte order
+    pub fn as_bytes(&self) -> &[u8] {
+        self.dyn_image.as_bytes()
+    }
+
+    /// Convert the pixels to the given color type, the metadata is kept
+    pub fn convert(&self, color: ColorType) -> Self {
+        let image = &self.dyn_image;
+        self.derive(match color {
+            _ if color == image.color() => image.clone(),
+            ColorType::L8 => image.to_luma8().into(),
+            ColorType::La8 => image.to_luma_alpha8().into(),
+            ColorType::Rgb8 => image.to_rgb8().into(),
+            ColorType::L16 => image.to_luma16().into(),
+            ColorType::La16 => image.to_luma_alpha16().into(),
+            ColorType::Rgb16 => image.to_rgb16().into(),
+            ColorType::Rgba16 => image.to_rgba16().into(),
+            ColorType::Rgb32F => image.to_rgb32f().into(),
+            ColorType::Rgba32F => image.to_rgba32f().into(),
+            _ => image.to_rgba8().into(),
+        })
+    }
+
+    /// Get the pixel at the given position as 8-bit RGBA, `None` if it is outside the image
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Rgba<u8>> {
+        self.dyn_image.in_bounds(x, y).then(|| self.dyn_image.get_pixel(x, y))
+    }
+
+    /// Set the pixel at the given position from 8-bit RGBA, converted to the color type of the image.
+    /// Returns `false` if it is outside the image
+    pub fn put_pixel(&mut self, x: u32, y: u32, pixel: Rgba<u8>) -> bool {
+        let inside = self.dyn_image.in_bounds(x, y);
+        if inside {
+            self.dyn_image.put_pixel(x, y, pixel);
+        }
+        inside
     }
 
     /// Get the dimensions of the image, in pixels
@@ -28,6 +176,34 @@ impl ImageWrapper {
         self.dyn_image.dimensions()
     }
 
+    /// Apply the EXIF orientation to the pixels, the orientation of the returned image is reset to `1`
+    pub fn orient(&self) -> Self {
+        let oriented = match self.metadata.orientation() {
+            2 => self.flip(true),
+            3 => self.rotate(2),
+            4 => self.flip(false),
+            5 => self.rotate(1).flip(true),
+            6 => self.rotate(1),
+            7 => self.rotate(3).flip(true),
+            8 => self.rotate(3),
+            _ => return self.derive(self.dyn_image.clone()),
+        };
+        oriented.with_metadata(self.metadata.upright())
+    }
+
+    /// Convert the pixels from the given ICC profile to sRGB, the profile is dropped from the metadata of the returned image
+    ///
+    /// An image without a profile is assumed to be sRGB already, pass `None` to copy it as is
+    pub fn to_srgb(&self, profile: Option<&Profile>) -> Self {
+        match profile {
+            Some(profile) => self.derive(profile.to_srgb(&self.dyn_image)).with_metadata(Metadata {
+                icc: None,
+                ..self.metadata.as_ref().clone()
+            }),
+            None => self.derive(self.dyn_image.clone()),
+        }
+    }
+
     /// Get the color type of the image
     pub fn color(&self) -> ColorType {
         self.dyn_image.color()
@@ -40,81 +216,368 @@ impl ImageWrapper {
 
     /// Resize this image using the specified filter algorithm. Returns a new image. The image's aspect ratio is preserved. The image is scaled to the maximum possible size that fits within the bounds specified by `nw` and `nh`.
     pub fn resize_to_fit(&self, nw: u32, nh: u32, filter: FilterType) -> Self {
-        Self {
-            dyn_image: self.dyn_image.resize(nw, nh, filter)
-        }
+        self.derive(self.dyn_image.resize(nw, nh, filter))
     }
 
     /// Resize this image using the specified filter algorithm. Returns a new image. The image's aspect ratio is preserved. The image is scaled to the maximum possible size that fits within the larger (relative to aspect ratio) of the bounds specified by `nw` and `nh`, then cropped to fit within the bounds specified by `nw` and `nh`.
     pub fn resize_to_cover(&self, nw: u32, nh: u32, filter: FilterType) -> Self {
-        Self {
-            dyn_image: self.dyn_image.resize_to_fill(nw, nh, filter)
-        }
+        self.derive(self.dyn_image.resize_to_fill(nw, nh, filter))
     }
 
     /// Resize this image using the specified filter algorithm. Returns a new image. Does not preserve aspect ratio. nw and nh are the new image's dimensions.
     pub fn resize_exact(&self, nw: u32, nh: u32, filter: FilterType) -> Self {
-        Self {
-            dyn_image: self.dyn_image.resize_exact(nw, nh, filter)
-        }
+        self.derive(self.dyn_image.resize_exact(nw, nh, filter))
     }
 
     /// Rotate this image by 90 degrees clockwise. Returns a new image
     pub fn rotate(&self, quarter: u8) -> Self {
-        Self {
-            dyn_image: match quarter % 4 {
-                1 => self.dyn_image.rotate90(),
-                2 => self.dyn_image.rotate180(),
-                3 => self.dyn_image.rotate270(),
-                _ => self.dyn_image.clone()
-            }
+        self.derive(match quarter % 4 {
+            1 => self.dyn_image.rotate90(),
+            2 => self.dyn_image.rotate180(),
+            3 => self.dyn_image.rotate270(),
+            _ => self.dyn_image.clone()
+        })
+    }
+
+    /// Rotate this image clockwise by any angle around its center. Returns a new image
+    ///
+    /// The canvas keeps its size unless `expand`, the uncovered area is filled with the background.
+    /// Color types without alpha get an alpha channel when the background is not opaque
+    pub fn rotate_by(&self, degrees: f64, expand: bool, background: Rgba<u8>, interpolation: Interpolation) -> Self {
+        let (w, h) = self.dimensions();
+        let turn = degrees.rem_euclid(360.0);
+        if turn % 90.0 == 0.0 && (expand || turn % 180.0 == 0.0 || w == h) {
+            return self.rotate((turn / 90.0) as u8);
         }
+
+        let (sin, cos) = turn.to_radians().sin_cos();
+        let (ow, oh) = if expand {
+            // the tolerance keeps rounding noise from adding a row or column
+            let fit = |a: f64, b: f64| (a + b - 1e-6).ceil().max(1.0) as u32;
+            (fit(w as f64 * cos.abs(), h as f64 * sin.abs()), fit(w as f64 * sin.abs(), h as f64 * cos.abs()))
+        } else {
+            (w, h)
+        };
+
+        // sample in premultiplied alpha, so that the background does not bleed into the edges
+        let premultiply = |[r, g, b, a]: [f32; 4]| [r * a, g * a, b * a, a];
+        let mut source = self.dyn_image.to_rgba32f();
+        source.pixels_mut().for_each(|p| p.0 = premultiply(p.0));
+        let background_sample = premultiply(background.0.map(|c| c as f32 / 255.0));
+        let at = |x: i64, y: i64| {
+            if x < 0 || y < 0 || x >= w as i64 || y >= h as i64 {
+                background_sample
+            } else {
+                source.get_pixel(x as u32, y as u32).0
+            }
+        };
+
+        let (cx, cy, ocx, ocy) = (w as f64 / 2.0, h as f64 / 2.0, ow as f64 / 2.0, oh as f64 / 2.0);
+        let rotated = Rgba32FImage::from_fn(ow, oh, |x, y| {
+            // map the center of the target pixel back into the source, in source pixel indices
+            let (dx, dy) = (x as f64 + 0.5 - ocx, y as f64 + 0.5 - ocy);
+            let (x0, wx, nx) = interpolation.taps(dx * cos + dy * sin + cx - 0.5);
+            let (y0, wy, ny) = interpolation.taps(-dx * sin + dy * cos + cy - 0.5);
+
+            let mut sum = [0.0f32; 4];
+            for (j, wy) in wy.iter().take(ny).enumerate() {
+                for (i, wx) in wx.iter().take(nx).enumerate() {
+                    let sample = at(x0 + i as i64, y0 + j as i64);
+                    sum.iter_mut().zip(sample).for_each(|(s, c)| *s += c * wx * wy);
+                }
+            }
+
+            let alpha = sum[3].clamp(0.0, 1.0);
+            let color = |c: f32| if alpha > 0.0 { c.max(0.0) / alpha } else { 0.0 };
+            Rgba([color(sum[0]), color(sum[1]), color(sum[2]), alpha])
+        });
+
+        let color = match self.color() {
+            _ if background[3] == 255 => self.color(),
+            ColorType::L8 => ColorType::La8,
+            ColorType::Rgb8 => ColorType::Rgba8,
+            ColorType::L16 => ColorType::La16,
+            ColorType::Rgb16 => ColorType::Rgba16,
+            ColorType::Rgb32F => ColorType::Rgba32F,
+            color => color,
+        };
+        self.derive(Self::new(DynamicImage::ImageRgba32F(rotated)).convert(color).dyn_image)
     }
 
     /// Flip this image horizontally or vertically. Returns a new image
     pub fn flip(&self, horizontal: bool) -> Self {
-        Self {
-            dyn_image: if horizontal {
-                self.dyn_image.fliph()
-            } else {
-                self.dyn_image.flipv()
-            }
-        }
+        self.derive(if horizontal {
+            self.dyn_image.fliph()
+        } else {
+            self.dyn_image.flipv()
+        })
     }
 
     // Get a cut-out of this image delimited by the bounding rectangle. Returns a new image
     pub fn crop(&self, x: u32, y: u32, w: u32, h: u32) -> Self {
-        Self {
-            dyn_image: self.dyn_image.crop_imm(x, y, w, h)
-        }
+        self.derive(self.dyn_image.crop_imm(x, y, w, h))
+    }
+
+    /// Get a copy of the image as 8-bit RGBA, e.g. for building an animation frame
+    pub fn to_rgba8(&self) -> RgbaImage {
+        self.dyn_image.to_rgba8()
+    }
+
+    /// Get the image in a color type the encoder of `format` can represent, borrowed if no conversion is needed
+    ///
+    /// Floating point samples are clamped to `0-1` when converted to integers, 8-bit samples are promoted for the encoders of wider formats
+    fn encodable(&self, format: &ImageOutputFormat) -> Cow<'_, DynamicImage> {
+        let image = &self.dyn_image;
+        let color = image.color();
+        let converted: DynamicImage = match format {
+            // the icons are stored as png, which most readers only accept in 8-bit rgba
+            ImageOutputFormat::Ico => match color {
+                ColorType::Rgba8 => return Cow::Borrowed(image),
+                _ => image.to_rgba8().into(),
+            },
+            ImageOutputFormat::Png => match color {
+                ColorType::Rgb32F => image.to_rgb16().into(),
+                ColorType::Rgba32F => image.to_rgba16().into(),
+                _ => return Cow::Borrowed(image),
+            },
+            ImageOutputFormat::Jpeg(_) | ImageOutputFormat::Bmp | ImageOutputFormat::Tga | ImageOutputFormat::WebP
+            | ImageOutputFormat::Pnm(PnmSubtype::ArbitraryMap) => match color {
+                ColorType::L8 | ColorType::La8 | ColorType::Rgb8 | ColorType::Rgba8 => return Cow::Borrowed(image),
+                ColorType::L16 => image.to_luma8().into(),
+                ColorType::La16 => image.to_luma_alpha8().into(),
+                color if color.has_alpha() => image.to_rgba8().into(),
+                _ => image.to_rgb8().into(),
+            },
+            ImageOutputFormat::Tiff => match color {
+                ColorType::L8 | ColorType::Rgb8 | ColorType::Rgba8 | ColorType::L16 | ColorType::Rgb16 | ColorType::Rgba16 => return Cow::Borrowed(image),
+                ColorType::La8 => image.to_rgba8().into(),
+                ColorType::Rgb32F => image.to_rgb16().into(),
+                _ => image.to_rgba16().into(),
+            },
+            ImageOutputFormat::Qoi => match color {
+                ColorType::Rgb8 | ColorType::Rgba8 => return Cow::Borrowed(image),
+                color if color.has_alpha() => image.to_rgba8().into(),
+                _ => image.to_rgb8().into(),
+            },
+            ImageOutputFormat::Farbfeld => match color {
+                ColorType::Rgba16 => return Cow::Borrowed(image),
+                _ => image.to_rgba16().into(),
+            },
+            ImageOutputFormat::OpenExr => match color {
+                ColorType::Rgb32F | ColorType::Rgba32F => return Cow::Borrowed(image),
+                color if color.has_alpha() => image.to_rgba32f().into(),
+                _ => image.to_rgb32f().into(),
+            },
+            ImageOutputFormat::Pnm(PnmSubtype::Graymap(_)) => match color {
+                ColorType::L8 => return Cow::Borrowed(image),
+                _ => image.to_luma8().into(),
+            },
+            ImageOutputFormat::Pnm(PnmSubtype::Pixmap(_)) => match color {
+                ColorType::Rgb8 => return Cow::Borrowed(image),
+                _ => image.to_rgb8().into(),
+            },
+            // the gif encoder quantizes any color type, pbm is written by hand
+            _ => return Cow::Borrowed(image),
+        };
+        Cow::Owned(converted)
+    }
+
+    /// Encode this image as a PNG with the given compression level and filter strategy. Returns a Vec<u8>
+    pub fn png(&self, compression: CompressionType, filter: PngFilterType) -> Result<Vec<u8>, ImageError> {
+        let mut buf = vec![];
+        let (w, h) = self.dimensions();
+        let image = self.encodable(&ImageOutputFormat::Png);
+        PngEncoder::new_with_quality(&mut buf, compression, filter)
+            .write_image(image.as_bytes(), w, h, image.color())
+            .map(|_| buf)
+    }
+
+    /// Encode this image as a lossless WebP. Returns a Vec<u8>
+    pub fn webp(&self) -> Result<Vec<u8>, ImageError> {
+        let mut buf = vec![];
+        let (w, h) = self.dimensions();
+        let image = self.encodable(&ImageOutputFormat::WebP);
+        WebPEncoder::new_lossless(&mut buf)
+            .encode(image.as_bytes(), w, h, image.color())
+            .map(|_| buf)
+    }
+
+    /// Encode this image as a Radiance HDR, the samples are converted to 32-bit floating point RGB. Returns a Vec<u8>
+    pub fn hdr(&self) -> Result<Vec<u8>, ImageError> {
+        let mut buf = vec![];
+        let (w, h) = self.dimensions();
+        let rgb = self.dyn_image.to_rgb32f();
+        let pixels: Vec<_> = rgb.pixels().copied().collect();
+        HdrEncoder::new(&mut buf)
+            .encode(&pixels, w as usize, h as usize)
+            .map(|_| buf)
+    }
+
+    /// Encode this image as an AVIF with the given quality of the color and alpha channels, and the encoder speed within `1-10`. Returns a Vec<u8>
+    #[cfg(feature = "avif")]
+    pub fn avif(&self, quality: u8, alpha_quality: u8, speed: u8) -> Result<Vec<u8>, ImageError> {
+        use image::error::EncodingError;
+        use ravif::{Encoder, Img, RGB8, RGBA8};
+
+        let (w, h) = (self.dimensions().0 as usize, self.dimensions().1 as usize);
+        let encoder = Encoder::new()
+            .with_quality(quality as f32)
+            .with_alpha_quality(alpha_quality as f32)
+            .with_speed(speed);
+
+        let encoded = if self.color().has_alpha() {
+            let pixels: Vec<RGBA8> = self.dyn_image.to_rgba8().pixels().map(|p| RGBA8::new(p[0], p[1], p[2], p[3])).collect();
+            encoder.encode_rgba(Img::new(&pixels[..], w, h))
+        } else {
+            let pixels: Vec<RGB8> = self.dyn_image.to_rgb8().pixels().map(|p| RGB8::new(p[0], p[1], p[2])).collect();
+            encoder.encode_rgb(Img::new(&pixels[..], w, h))
+        };
+        encoded
+            .map(|encoded| encoded.avif_file)
+            .map_err(|err| ImageError::Encoding(EncodingError::new(ImageFormat::Avif.into(), err)))
     }
 
     /// Encode this image and get the encoded bytes. Returns a Vec<u8>
     pub fn buffer(&self, format: ImageOutputFormat) -> Result<Vec<u8>, ImageError> {
+        if let ImageOutputFormat::Pnm(PnmSubtype::Bitmap(encoding)) = format {
+            return Ok(pbm(&self.dyn_image, encoding));
+        }
+
         let mut buf = Cursor::new(vec![]);
-        match self.dyn_image.write_to(&mut buf, format) {
+        match self.encodable(&format).write_to(&mut buf, format) {
             Ok(_) => Ok(buf.into_inner()),
             Err(err) => Err(err)
         }
     }
 }
 
+/// Write a PBM by hand, as the encoder of image rejects every color type for it. Pixels darker than the middle gray become black
+fn pbm(image: &DynamicImage, encoding: SampleEncoding) -> Vec<u8> {
+    let luma = image.to_luma8();
+    let (w, h) = luma.dimensions();
+    let black = |x: u32, y: u32| luma.get_pixel(x, y)[0] < 128;
+
+    match encoding {
+        SampleEncoding::Binary => {
+            let mut buf = format!("P4\n{} {}\n", w, h).into_bytes();
+            for y in 0..h {
+                // rows are packed into bytes, the first pixel in the most significant bit
+                for x in (0..w).step_by(8) {
+                    buf.push((x..(x + 8).min(w)).fold(0u8, |byte, px| byte | ((black(px, y) as u8) << (7 - (px - x)))));
+                }
+            }
+            buf
+        }
+        SampleEncoding::Ascii => {
+            let mut text = format!("P1\n{} {}\n", w, h);
+            for y in 0..h {
+                let row: Vec<&str> = (0..w).map(|x| if black(x, y) { "1" } else { "0" }).collect();
+                text.push_str(&row.join(" "));
+                text.push('\n');
+            }
+            text.into_bytes()
+        }
+    }
+}
+
 #[cfg(test)]
 mod unit_test {
     use std::fs::File;
     use std::io::{Read};
     use super::*;
 
+    #[test]
+    fn pbm_roundtrip() {
+        let checker = DynamicImage::ImageLuma8(image::GrayImage::from_fn(11, 3, |x, y| image::Luma([if (x + y) % 2 == 0 { 0 } else { 255 }])));
+        for encoding in [SampleEncoding::Binary, SampleEncoding::Ascii] {
+            let encoded = ImageWrapper::new(checker.clone()).buffer(ImageOutputFormat::Pnm(PnmSubtype::Bitmap(encoding))).unwrap();
+            let decoded = image::load_from_memory_with_format(&encoded, ImageFormat::Pnm).unwrap();
+            assert_eq!(decoded.to_luma8(), checker.to_luma8());
+        }
+    }
+
+    #[test]
+    fn hdr_roundtrip() {
+        let hdr = DynamicImage::ImageRgb32F(image::Rgb32FImage::from_pixel(3, 2, image::Rgb([4.0, 0.5, 0.25])));
+        let encoded = ImageWrapper::new(hdr).hdr().unwrap();
+        let decoder = image::codecs::hdr::HdrDecoder::new(Cursor::new(&encoded)).unwrap();
+        assert_eq!((decoder.metadata().width, decoder.metadata().height), (3, 2));
+        // radiance stores a shared exponent, values beyond 1 survive
+        assert_eq!(decoder.read_image_hdr().unwrap()[0].0, [4.0, 0.5, 0.25]);
+
+        let rgba = DynamicImage::new_rgba8(5, 5);
+        assert!(ImageWrapper::new(rgba).hdr().is_ok());
+    }
+
+    #[test]
+    fn float_is_clamped() {
+        let hdr = DynamicImage::ImageRgb32F(image::Rgb32FImage::from_pixel(2, 2, image::Rgb([4.0, 0.5, -1.0])));
+        let png = ImageWrapper::new(hdr).buffer(ImageOutputFormat::Png).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap();
+        assert_eq!(decoded.color(), ColorType::Rgb16);
+        assert_eq!(decoded.to_rgb16()[(0, 0)].0, [65535, 32768, 0]);
+    }
+
+    #[test]
+    fn raw_roundtrip() {
+        let colors = [
+            ColorType::L8, ColorType::La8, ColorType::Rgb8, ColorType::Rgba8, ColorType::L16,
+            ColorType::La16, ColorType::Rgb16, ColorType::Rgba16, ColorType::Rgb32F, ColorType::Rgba32F,
+        ];
+        let source = ImageWrapper::new(DynamicImage::ImageRgba8(RgbaImage::from_fn(3, 2, |x, y| Rgba([x as u8 * 80, y as u8 * 200, 255, 128]))));
+
+        for color in colors {
+            let converted = source.convert(color);
+            assert_eq!(converted.color(), color);
+            let raw = ImageWrapper::from_raw(converted.as_bytes(), 3, 2, color).unwrap();
+            assert_eq!(raw.as_bytes(), converted.as_bytes(), "{:?}", color);
+        }
+        assert!(ImageWrapper::from_raw(&[0; 17], 3, 2, ColorType::Rgb8).is_none());
+    }
+
+    #[test]
+    fn put_pixel() {
+        let mut wrapper = ImageWrapper::new(DynamicImage::new_rgb16(2, 2));
+        assert!(wrapper.put_pixel(1, 0, Rgba([255, 128, 0, 255])));
+        assert!(!wrapper.put_pixel(2, 0, Rgba([255, 128, 0, 255])));
+        assert_eq!(wrapper.pixel(1, 0), Some(Rgba([255, 128, 0, 255])));
+        assert_eq!(wrapper.pixel(0, 2), None);
+        assert_eq!(wrapper.dyn_image.to_rgb16()[(1, 0)].0, [65535, 32896, 0]);
+    }
+
+    #[test]
+    fn rotate_by() {
+        let source = ImageWrapper::new(DynamicImage::ImageRgb8(image::RgbImage::from_fn(10, 6, |x, y| image::Rgb([x as u8 * 20, y as u8 * 40, 7]))));
+        let white = Rgba([255, 255, 255, 255]);
+
+        // quarter turns are exact
+        let quarter = source.rotate_by(-270.0, true, white, Interpolation::Bicubic);
+        assert_eq!(quarter.dyn_image, source.rotate(1).dyn_image);
+
+        // the canvas is kept, the corners are filled and the center stays in place
+        let kept = source.rotate_by(30.0, false, white, Interpolation::Bilinear);
+        assert_eq!((kept.dimensions(), kept.color()), ((10, 6), ColorType::Rgb8));
+        assert_eq!(kept.pixel(0, 5), Some(white));
+        let center = kept.rotate_by(-30.0, false, white, Interpolation::Bilinear).pixel(5, 3).unwrap();
+        assert!(center.0.iter().zip(source.pixel(5, 3).unwrap().0).all(|(a, b)| a.abs_diff(b) <= 12), "{:?}", center);
+
+        // the canvas grows to fit, a transparent background adds an alpha channel
+        let expanded = source.rotate_by(45.0, true, Rgba([0, 0, 0, 0]), Interpolation::Nearest);
+        assert_eq!((expanded.dimensions(), expanded.color()), ((12, 12), ColorType::Rgba8));
+        assert_eq!(expanded.pixel(0, 0), Some(Rgba([0, 0, 0, 0])));
+        assert_eq!(expanded.pixel(6, 6).unwrap()[3], 255);
+    }
+
     #[test]
     fn t() {
-        let img = r##"D:\@fastigiata\image\__test__\dot.png"##;
+        let img = concat!(env!("CARGO_MANIFEST_DIR"), "/__test__/_source/tree.png");
 
         let mut buf = vec![];
         File::open(img).unwrap().read_to_end(&mut buf).unwrap();
 
         // let img = image::open(img).unwrap();
         // let img = ImageWrapper::new(img);
-        let img = ImageWrapper::load(buf, None).unwrap();
+        let img = ImageWrapper::load(&buf, ImageFormat::Png, Limits::default()).unwrap();
 
         println!("{:?}", img.buffer(ImageOutputFormat::Png).unwrap());
     }
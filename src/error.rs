@@ -0,0 +1,101 @@
+use image::ImageError;
+use napi::Error;
+
+/// Stable error codes, exposed to JavaScript as the `code` property of the thrown `Error`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// the resize filter name is not one of the supported filters
+    InvalidFilter,
+    /// the ico conversion strategy is not in the format of `<mode>_<filter>`
+    InvalidStrategy,
+    /// the jpeg quality is not within `1-100`
+    InvalidQuality,
+    /// the number of quarter rotations is not within `0-3`
+    InvalidQuarter,
+    /// the pnm subtype is not one of `pbm`, `pgm`, `ppm` or `pam`
+    InvalidPnmSubtype,
+    /// the color is not a CSS color, e.g. `#rrggbbaa`, `rgb()` or a named color
+    InvalidColor,
+    /// the crop rectangle is not fully inside the image
+    CropOutOfBounds,
+    /// the pixel position is not inside the image
+    PixelOutOfBounds,
+    /// a target width or height is zero
+    ZeroSize,
+    /// the frame index is not within the frames of an animation
+    FrameOutOfBounds,
+    /// the image is too large for the target format
+    TooLarge,
+    /// the image data could not be decoded
+    Decode,
+    /// the image could not be encoded
+    Encode,
+    /// the format or color type is not supported
+    Unsupported,
+    /// a parameter passed to the underlying codec is invalid
+    InvalidParameter,
+    /// a resource limit was exceeded
+    LimitExceeded,
+    /// an io error occurred
+    Io,
+    /// this should never happen
+    Internal,
+}
+
+impl AsRef<str> for ErrorCode {
+    fn as_ref(&self) -> &str {
+        match self {
+            ErrorCode::InvalidFilter => "ERR_INVALID_FILTER",
+            ErrorCode::InvalidStrategy => "ERR_INVALID_STRATEGY",
+            ErrorCode::InvalidQuality => "ERR_INVALID_QUALITY",
+            ErrorCode::InvalidQuarter => "ERR_INVALID_QUARTER",
+            ErrorCode::InvalidPnmSubtype => "ERR_INVALID_PNM_SUBTYPE",
+            ErrorCode::InvalidColor => "ERR_INVALID_COLOR",
+            ErrorCode::CropOutOfBounds => "ERR_CROP_OUT_OF_BOUNDS",
+            ErrorCode::PixelOutOfBounds => "ERR_PIXEL_OUT_OF_BOUNDS",
+            ErrorCode::ZeroSize => "ERR_ZERO_SIZE",
+            ErrorCode::FrameOutOfBounds => "ERR_FRAME_OUT_OF_BOUNDS",
+            ErrorCode::TooLarge => "ERR_TOO_LARGE",
+            ErrorCode::Decode => "ERR_DECODE",
+            ErrorCode::Encode => "ERR_ENCODE",
+            ErrorCode::Unsupported => "ERR_UNSUPPORTED",
+            ErrorCode::InvalidParameter => "ERR_INVALID_PARAMETER",
+            ErrorCode::LimitExceeded => "ERR_LIMIT_EXCEEDED",
+            ErrorCode::Io => "ERR_IO",
+            ErrorCode::Internal => "ERR_INTERNAL",
+        }
+    }
+}
+
+/// A `Result` whose error carries an `ErrorCode`
+pub type Result<T> = napi::Result<T, ErrorCode>;
+
+/// Create an error with a message in the format of `<summary> | <detail>`
+pub fn error(code: ErrorCode, summary: &str, detail: impl std::fmt::Display) -> Error<ErrorCode> {
+    Error::new(code, format!("{} | {}", summary, detail))
+}
+
+/// Convert an `ImageError` from the underlying library into an error with the matching `ErrorCode`
+pub fn image_error(err: ImageError) -> Error<ErrorCode> {
+    match err {
+        ImageError::Decoding(_) => error(ErrorCode::Decode, "Decode failed", err),
+        ImageError::Encoding(_) => error(ErrorCode::Encode, "Encode failed", err),
+        ImageError::Parameter(_) => error(ErrorCode::InvalidParameter, "Invalid parameter", err),
+        ImageError::Limits(_) => error(ErrorCode::LimitExceeded, "Limit exceeded", err),
+        ImageError::Unsupported(_) => error(ErrorCode::Unsupported, "Unsupported", err),
+        ImageError::IoError(_) => error(ErrorCode::Io, "Io error", err),
+    }
+}
+
+/// Convert an `ImageError` raised while decoding from memory, where an io error can only mean truncated data
+pub fn decode_error(err: ImageError) -> Error<ErrorCode> {
+    match err {
+        ImageError::IoError(err) => error(ErrorCode::Decode, "Decode failed", err),
+        err => image_error(err),
+    }
+}
+
+/// Convert an io error from reading or writing a file, naming the path in the detail
+pub fn io_error(err: std::io::Error, path: &str) -> Error<ErrorCode> {
+    error(ErrorCode::Io, "Io error", format!("{}: {}", path, err))
+}
@@ -3,9 +3,18 @@
 #[macro_use]
 extern crate napi_derive;
 
+mod animation;
+#[cfg(feature = "avif")]
+mod avif;
+mod binary;
+mod color;
 mod common;
 mod core;
+mod error;
 mod loader;
+mod metadata;
+mod probe;
+mod task;
 
 #[napi]
 pub fn sum(a: i32, b: i32) -> i32 {
@@ -1,7 +1,66 @@
-use image::{ImageFormat};
-use napi::{Error, Result};
+use std::io::Cursor;
+use std::sync::Arc;
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::io::Limits;
+use image::{AnimationDecoder, Delay, ImageDecoder, ImageFormat};
+use napi::bindgen_prelude::AsyncTask;
+use crate::animation::{AnimatedImage, AnimationFrame};
+use crate::binary::BinaryInput;
 use crate::core::ImageWrapper;
-use crate::common::CommonImage;
+use crate::common::{check_size, parse_color, parse_color_type, CommonImage};
+use crate::error::{decode_error, error, io_error, ErrorCode, Result};
+use crate::metadata::Metadata;
+use crate::probe::{detect_format, format_name, gif_layouts, parse_format, png_layouts, probe, webp_header, webp_layouts, Disposal, FrameLayout, ImageInfo};
+use crate::task::ImageTask;
+
+/// Options used when loading an image
+#[napi(object)]
+pub struct LoadOptions {
+    /// The maximum allowed image width in pixels, checked against the header before decoding. The default is no limit
+    pub max_width: Option<u32>,
+    /// The maximum allowed image height in pixels, checked against the header before decoding. The default is no limit
+    pub max_height: Option<u32>,
+    /// The maximum number of bytes the decoder may allocate. The default is 512 MiB
+    pub max_alloc: Option<i64>,
+    /// Whether to apply the EXIF orientation to the pixels, so that the image is upright. The default is `false`
+    pub auto_orient: Option<bool>,
+}
+
+impl LoadOptions {
+    /// Get the decoder limits, the defaults of the underlying library are used for absent options
+    fn limits(options: &Option<LoadOptions>) -> Result<Limits> {
+        let mut limits = Limits::default();
+
+        if let Some(options) = options {
+            limits.max_image_width = options.max_width;
+            limits.max_image_height = options.max_height;
+            if let Some(max_alloc) = options.max_alloc {
+                limits.max_alloc = Some(u64::try_from(max_alloc).map_err(|_| {
+                    error(ErrorCode::InvalidParameter, "Invalid parameter", format!("maxAlloc must not be negative, got {}", max_alloc))
+                })?);
+            }
+        }
+
+        Ok(limits)
+    }
+}
+
+/// Check the limits against the header of a WebP, since its decoder decodes the whole image on creation
+fn check_webp(buffer: &[u8], limits: &Limits) -> Result<()> {
+    let (w, h, color) = webp_header(buffer)?;
+    let exceeded = |detail: String| Err(error(ErrorCode::LimitExceeded, "Limit exceeded", detail));
+
+    match (limits.max_image_width, limits.max_image_height, limits.max_alloc) {
+        (Some(max), _, _) if w > max => exceeded(format!("width {} exceeds {}", w, max)),
+        (_, Some(max), _) if h > max => exceeded(format!("height {} exceeds {}", h, max)),
+        (_, _, Some(max)) if w as u64 * h as u64 * color.bytes_per_pixel() as u64 > max => {
+            exceeded(format!("{}x{} exceeds the allocation limit of {} bytes", w, h, max))
+        }
+        _ => Ok(()),
+    }
+}
 
 /// `ImageLoader` provides several way to load image binary into a `CommonImage`
 #[napi]
@@ -10,106 +69,803 @@ pub struct ImageLoader {}
 #[napi]
 impl ImageLoader {
     #[inline]
-    fn load(buffer: Vec<u8>, format: Option<ImageFormat>) -> Result<CommonImage> {
-        match ImageWrapper::load(buffer, format) {
-            Ok(iw) => Ok(CommonImage::new(iw)),
-            Err(err) => Err(Error::from_reason(format!("{}", err))),
+    fn load(buffer: &[u8], format: Option<ImageFormat>, options: Option<LoadOptions>) -> Result<CommonImage> {
+        let limits = LoadOptions::limits(&options)?;
+
+        let format = match format {
+            Some(f) => f,
+            None => image::guess_format(buffer).map_err(decode_error)?,
+        };
+        if format == ImageFormat::WebP {
+            check_webp(buffer, &limits)?;
+        }
+
+        let auto_orient = options.and_then(|options| options.auto_orient).unwrap_or(false);
+        match ImageWrapper::load(buffer, format, limits) {
+            Ok(iw) => {
+                let iw = iw.with_metadata(Metadata::read(buffer, format));
+                Ok(CommonImage::new(if auto_orient { iw.orient() } else { iw }))
+            }
+            Err(err) => Err(decode_error(err)),
+        }
+    }
+
+    /// Read and decode a file, the format is sniffed from the content and falls back to the extension
+    fn load_path(path: &str, options: Option<LoadOptions>) -> Result<CommonImage> {
+        let buffer = std::fs::read(path).map_err(|err| io_error(err, path))?;
+        let format = match detect_format(&buffer) {
+            Some(format) => format,
+            None => ImageFormat::from_path(path).map_err(|_| error(ErrorCode::Unsupported, "Unsupported format", path))?,
+        };
+        Self::load(&buffer, Some(format), options)
+    }
+
+    /// Wrap raw pixel data, checking its length against the dimensions and color type
+    fn load_raw(data: &[u8], width: u32, height: u32, color_type: &str) -> Result<CommonImage> {
+        let color = parse_color_type(color_type)?;
+        check_size(width, height)?;
+
+        ImageWrapper::from_raw(data, width, height, color).map(CommonImage::new).ok_or_else(|| {
+            let expected = width as u64 * height as u64 * color.bytes_per_pixel() as u64;
+            error(
+                ErrorCode::InvalidParameter,
+                "Invalid parameter",
+                format!("expected {} bytes for {}x{} {}, got {}", expected, width, height, color_type, data.len()),
+            )
+        })
+    }
+
+    /// Decode every frame of an animation, a still image results in a single frame
+    fn load_frames(buffer: &[u8], format: ImageFormat, options: Option<LoadOptions>) -> Result<AnimatedImage> {
+        let limits = LoadOptions::limits(&options)?;
+        let reader = Cursor::new(buffer);
+
+        let (frames, layouts) = match format {
+            ImageFormat::Gif => {
+                let mut decoder = GifDecoder::new(reader).map_err(decode_error)?;
+                decoder.set_limits(limits.clone()).map_err(decode_error)?;
+                (decoder.into_frames(), gif_layouts(buffer))
+            }
+            ImageFormat::Png => {
+                let decoder = PngDecoder::with_limits(reader, limits.clone()).map_err(decode_error)?;
+                (decoder.apng().into_frames(), png_layouts(buffer))
+            }
+            ImageFormat::WebP => {
+                check_webp(buffer, &limits)?;
+                let decoder = WebPDecoder::new(reader).map_err(decode_error)?;
+                (decoder.into_frames(), webp_layouts(buffer))
+            }
+            _ => return Err(error(ErrorCode::Unsupported, "Unsupported", format!("{} is not an animated format", format_name(format)))),
+        };
+
+        let frames = AnimatedImage::collect(frames, layouts, limits.max_alloc)?;
+        if !frames.is_empty() {
+            return Ok(AnimatedImage::new(frames));
         }
+
+        // the decoders yield no frames for a still PNG or WebP
+        let still = ImageWrapper::load(buffer, format, limits).map_err(decode_error)?;
+        let (width, height) = still.dimensions();
+        Ok(AnimatedImage::new(vec![AnimationFrame {
+            image: Arc::new(still),
+            delay: Delay::from_numer_denom_ms(0, 1),
+            layout: FrameLayout { left: 0, top: 0, width, height, disposal: Disposal::None },
+        }]))
+    }
+
+    /// Decode on the libuv thread pool instead of blocking the event loop
+    #[inline]
+    fn load_async(buffer: BinaryInput, format: Option<ImageFormat>, options: Option<LoadOptions>) -> AsyncTask<ImageTask<CommonImage>> {
+        ImageTask::spawn(move || Self::load(&buffer, format, options))
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Makes an educated guess about the image format
+    /// Read the format, dimensions and color type of an image from its header, without decoding the pixel data
     #[napi]
-    pub fn auto_guess(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, None)
+    pub fn probe(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+    ) -> Result<ImageInfo> {
+        probe(&buffer)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a PNG
+    /// Create a `CommonImage` instance from a file, without reading it into JavaScript first
+    ///
+    /// ---
+    /// The format is sniffed from the content, and taken from the extension when the content has no magic bytes (TGA). A missing or unreadable file throws an `ERR_IO` error
     #[napi]
-    pub fn from_png(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Png))
+    pub fn from_path(path: String, options: Option<LoadOptions>) -> Result<CommonImage> {
+        Self::load_path(&path, options)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a JPEG
+    /// Async version of {@link fromPath}, the reading and decoding run on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_path_async(path: String, options: Option<LoadOptions>) -> AsyncTask<ImageTask<CommonImage>> {
+        ImageTask::spawn(move || Self::load_path(&path, options))
+    }
+
+    /// Create a `CommonImage` instance from raw pixel data, e.g. the `data` of an `ImageData` or the output of {@link CommonImage.toRaw}
+    ///
+    /// ---
+    /// The data is tightly packed rows of samples in the given color type, 16-bit and float samples in the platform byte order, so a `Uint16Array` or `Float32Array` can be passed directly.
+    /// The length of the data must be exactly `width * height * bytesPerPixel`, otherwise an `ERR_INVALID_PARAMETER` error is thrown
     #[napi]
-    pub fn from_jpeg(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Jpeg))
+    pub fn from_raw(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | Uint8ClampedArray | Uint16Array | Float32Array | ArrayBuffer")]
+        data: BinaryInput,
+        width: u32,
+        height: u32,
+        #[napi(ts_arg_type = "'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'")]
+        color_type: String,
+    ) -> Result<CommonImage> {
+        Self::load_raw(&data, width, height, &color_type)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a GIF
+    /// Create a `CommonImage` instance of the given size and color type, filled with a color. Transparent when `fillColor` is absent
+    ///
+    /// ---
+    /// `fillColor`: a CSS color, one of
+    /// - `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`
+    /// - `rgb(255, 128, 0)`, `rgba(255, 128, 0, 0.5)` or `rgb(100% 50% 0% / 50%)`
+    /// - a named color such as `white` or `rebeccapurple`, or `transparent`
+    ///
+    /// Anything else throws an `ERR_INVALID_COLOR` error. The alpha is dropped for color types without an alpha channel.
+    /// An image larger than 512 MiB throws an `ERR_LIMIT_EXCEEDED` error
     #[napi]
-    pub fn from_gif(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Gif))
+    pub fn blank(
+        width: u32,
+        height: u32,
+        #[napi(ts_arg_type = "'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'")]
+        color_type: String,
+        fill_color: Option<String>,
+    ) -> Result<CommonImage> {
+        let color = parse_color_type(&color_type)?;
+        let fill = parse_color(fill_color.as_deref().unwrap_or("transparent"))?;
+        check_size(width, height)?;
+
+        // the pixels are filled in 8-bit RGBA before the conversion
+        let max_alloc = Limits::default().max_alloc.unwrap_or(u64::MAX);
+        let bytes = width as u64 * height as u64 * (color.bytes_per_pixel() as u64 + 4);
+        if bytes > max_alloc {
+            return Err(error(ErrorCode::LimitExceeded, "Limit exceeded", format!("{}x{} exceeds the allocation limit of {} bytes", width, height, max_alloc)));
+        }
+        Ok(CommonImage::new(ImageWrapper::blank(width, height, color, fill)))
+    }
+
+    /// Sniff the format of an image from its magic bytes, without decoding it. Returns the name of the format, or `null` if it is not recognized
+    ///
+    /// ---
+    /// The name is the lowercase suffix of the corresponding `from*` method, see {@link ImageInfo.format}. TGA has no magic bytes and is never detected
+    #[napi(ts_return_type = "'png'|'jpeg'|'gif'|'webp'|'pnm'|'tiff'|'dds'|'bmp'|'ico'|'hdr'|'openexr'|'farbfeld'|'avif'|'qoi' | null")]
+    pub fn detect_format(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+    ) -> Option<String> {
+        detect_format(&buffer).map(|format| format_name(format).to_string())
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a WEBP
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`, decoded as the given format
+    ///
+    /// ---
+    /// `format`: a format name, file extension or MIME type, e.g. `png`, `.jpg` or `image/webp`. An unknown format throws an `ERR_UNSUPPORTED` error
     #[napi]
-    pub fn from_webp(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::WebP))
+    pub fn from_format(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        format: String,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(parse_format(&format)?), options)
+    }
+
+    /// Async version of {@link fromFormat}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_format_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        format: String,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        ImageTask::spawn(move || Self::load(&buffer, Some(parse_format(&format)?), options))
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a PNM
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Makes an educated guess about the image format
     #[napi]
-    pub fn from_pnm(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Pnm))
+    pub fn auto_guess(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, None, options)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a TIFF
+    /// Async version of {@link autoGuess}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn auto_guess_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, None, options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a PNG
     #[napi]
-    pub fn from_tiff(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Tiff))
+    pub fn from_png(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Png), options)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a TGA
+    /// Async version of {@link fromPng}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_png_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Png), options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a JPEG
     #[napi]
-    pub fn from_tga(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Tga))
+    pub fn from_jpeg(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Jpeg), options)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a DDS
+    /// Async version of {@link fromJpeg}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_jpeg_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Jpeg), options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a GIF
+    #[napi]
+    pub fn from_gif(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Gif), options)
+    }
+
+    /// Async version of {@link fromGif}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_gif_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Gif), options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a WEBP
+    #[napi]
+    pub fn from_webp(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::WebP), options)
+    }
+
+    /// Async version of {@link fromWebp}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_webp_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::WebP), options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a PNM
     #[napi]
-    pub fn from_dds(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Dds))
+    pub fn from_pnm(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Pnm), options)
+    }
+
+    /// Async version of {@link fromPnm}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_pnm_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Pnm), options)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a BMP
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a TIFF
     #[napi]
-    pub fn from_bmp(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Bmp))
+    pub fn from_tiff(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Tiff), options)
+    }
+
+    /// Async version of {@link fromTiff}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_tiff_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Tiff), options)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a ICO
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a TGA
     #[napi]
-    pub fn from_ico(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Ico))
+    pub fn from_tga(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Tga), options)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a Radiance HDR
+    /// Async version of {@link fromTga}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_tga_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Tga), options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a DDS
     #[napi]
-    pub fn from_hdr(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Hdr))
+    pub fn from_dds(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Dds), options)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a OpenEXR
+    /// Async version of {@link fromDds}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_dds_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Dds), options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a BMP
+    #[napi]
+    pub fn from_bmp(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Bmp), options)
+    }
+
+    /// Async version of {@link fromBmp}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_bmp_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Bmp), options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a ICO
+    #[napi]
+    pub fn from_ico(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Ico), options)
+    }
+
+    /// Async version of {@link fromIco}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_ico_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Ico), options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a Radiance HDR
+    #[napi]
+    pub fn from_hdr(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Hdr), options)
+    }
+
+    /// Async version of {@link fromHdr}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_hdr_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Hdr), options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a OpenEXR
     #[napi(js_name = "fromOpenEXR")]
-    pub fn from_openexr(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::OpenExr))
+    pub fn from_openexr(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::OpenExr), options)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a Farbfeld
+    /// Async version of {@link fromOpenEXR}, the decoding runs on the libuv thread pool
+    #[napi(js_name = "fromOpenEXRAsync", ts_return_type = "Promise<CommonImage>")]
+    pub fn from_openexr_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::OpenExr), options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a Farbfeld
     #[napi]
-    pub fn from_farbfeld(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Farbfeld))
+    pub fn from_farbfeld(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Farbfeld), options)
+    }
+
+    /// Async version of {@link fromFarbfeld}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_farbfeld_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Farbfeld), options)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a AVIF
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a AVIF
+    ///
+    /// ---
+    /// The decoder is only available when built with the `avif` feature, otherwise an `ERR_UNSUPPORTED` error is thrown.
+    /// 10 and 12-bit images are decoded to the 16-bit color types
     #[napi]
-    pub fn from_avif(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Avif))
+    pub fn from_avif(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Avif), options)
     }
 
-    /// Create a `CommonImage` instance from a byte slice. Assumes the image is a QOI
+    /// Async version of {@link fromAvif}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_avif_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Avif), options)
+    }
+
+    /// Create a `CommonImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a QOI
+    #[napi]
+    pub fn from_qoi(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Qoi), options)
+    }
+
+    /// Async version of {@link fromQoi}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<CommonImage>")]
+    pub fn from_qoi_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<CommonImage>> {
+        Self::load_async(buffer, Some(ImageFormat::Qoi), options)
+    }
+
+    /// Create an `AnimatedImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a GIF
+    #[napi]
+    pub fn frames_from_gif(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<AnimatedImage> {
+        Self::load_frames(&buffer, ImageFormat::Gif, options)
+    }
+
+    /// Async version of {@link framesFromGif}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<AnimatedImage>")]
+    pub fn frames_from_gif_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<AnimatedImage>> {
+        ImageTask::spawn(move || Self::load_frames(&buffer, ImageFormat::Gif, options))
+    }
+
+    /// Create an `AnimatedImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a PNG (APNG)
+    #[napi]
+    pub fn frames_from_png(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<AnimatedImage> {
+        Self::load_frames(&buffer, ImageFormat::Png, options)
+    }
+
+    /// Async version of {@link framesFromPng}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<AnimatedImage>")]
+    pub fn frames_from_png_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<AnimatedImage>> {
+        ImageTask::spawn(move || Self::load_frames(&buffer, ImageFormat::Png, options))
+    }
+
+    /// Create an `AnimatedImage` instance from a `Buffer`, `Uint8Array` or `ArrayBuffer`. Assumes the image is a WEBP
     #[napi]
-    pub fn from_qoi(buffer: Vec<u8>) -> Result<CommonImage> {
-        Self::load(buffer, Some(ImageFormat::Qoi))
+    pub fn frames_from_webp(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> Result<AnimatedImage> {
+        Self::load_frames(&buffer, ImageFormat::WebP, options)
+    }
+
+    /// Async version of {@link framesFromWebp}, the decoding runs on the libuv thread pool
+    #[napi(ts_return_type = "Promise<AnimatedImage>")]
+    pub fn frames_from_webp_async(
+        #[napi(ts_arg_type = "Buffer | Uint8Array | ArrayBuffer")]
+        buffer: BinaryInput,
+        options: Option<LoadOptions>,
+    ) -> AsyncTask<ImageTask<AnimatedImage>> {
+        ImageTask::spawn(move || Self::load_frames(&buffer, ImageFormat::WebP, options))
+    }
+}
+
+/// The `Array<number>` input the loaders took before `BinaryInput`, only built with the `bench` feature to compare against
+#[cfg(feature = "bench")]
+#[napi]
+impl ImageLoader {
+    /// {@link fromBmp} with the bytes marshalled one by one from an `Array<number>`
+    #[napi]
+    pub fn from_bmp_numbers(buffer: Vec<u8>) -> Result<CommonImage> {
+        Self::load(&buffer, Some(ImageFormat::Bmp), None)
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for byte in data {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            }
+        }
+        !crc
+    }
+
+    /// A PNG with a header claiming the given dimensions, followed by an empty image data chunk
+    fn png_header(w: u32, h: u32) -> Vec<u8> {
+        let mut ihdr = b"IHDR".to_vec();
+        ihdr.extend_from_slice(&w.to_be_bytes());
+        ihdr.extend_from_slice(&h.to_be_bytes());
+        ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut buf = b"\x89PNG\r\n\x1a\n".to_vec();
+        for chunk in [ihdr, b"IDAT".to_vec()] {
+            buf.extend_from_slice(&(chunk.len() as u32 - 4).to_be_bytes());
+            buf.extend_from_slice(&chunk);
+            buf.extend_from_slice(&crc32(&chunk).to_be_bytes());
+        }
+        buf
+    }
+
+    /// A Farbfeld with nothing but a header claiming the given dimensions
+    fn farbfeld_header(w: u32, h: u32) -> Vec<u8> {
+        let mut buf = b"farbfeld".to_vec();
+        buf.extend_from_slice(&w.to_be_bytes());
+        buf.extend_from_slice(&h.to_be_bytes());
+        buf
+    }
+
+    /// A WebP with nothing but an extended header claiming the given dimensions
+    fn webp_header(w: u32, h: u32) -> Vec<u8> {
+        let mut buf = b"RIFF\x16\x00\x00\x00WEBPVP8X\x0a\x00\x00\x00\x10\x00\x00\x00".to_vec();
+        buf.extend_from_slice(&(w - 1).to_le_bytes()[..3]);
+        buf.extend_from_slice(&(h - 1).to_le_bytes()[..3]);
+        buf
+    }
+
+    fn options(max_width: Option<u32>, max_height: Option<u32>, max_alloc: Option<i64>) -> Option<LoadOptions> {
+        Some(LoadOptions { max_width, max_height, max_alloc, auto_orient: None })
+    }
+
+    fn code(res: Result<CommonImage>) -> ErrorCode {
+        match res {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => err.status,
+        }
+    }
+
+    #[test]
+    fn load_path() {
+        let source = concat!(env!("CARGO_MANIFEST_DIR"), "/__test__/_source");
+        // tga has no magic bytes, the extension decides
+        for name in ["tree.png", "tree.tga"] {
+            let img = ImageLoader::load_path(&format!("{}/{}", source, name), None).unwrap();
+            assert_eq!(img.dimensions(), vec![157, 180]);
+        }
+        assert_eq!(code(ImageLoader::load_path(&format!("{}/missing.png", source), None)), ErrorCode::Io);
+    }
+
+    #[test]
+    fn bomb_exceeds_default_alloc() {
+        assert_eq!(code(ImageLoader::load(&png_header(60000, 60000), None, None)), ErrorCode::LimitExceeded);
+        assert_eq!(code(ImageLoader::load(&farbfeld_header(60000, 60000), None, None)), ErrorCode::LimitExceeded);
+        assert_eq!(code(ImageLoader::load(&webp_header(16384, 16384), None, None)), ErrorCode::LimitExceeded);
+    }
+
+    #[test]
+    fn bomb_exceeds_dimensions() {
+        let opts = || options(Some(4096), Some(4096), None);
+        assert_eq!(code(ImageLoader::load(&png_header(4097, 16), Some(ImageFormat::Png), opts())), ErrorCode::LimitExceeded);
+        assert_eq!(code(ImageLoader::load(&farbfeld_header(16, 4097), None, opts())), ErrorCode::LimitExceeded);
+        assert_eq!(code(ImageLoader::load(&webp_header(4097, 16), None, opts())), ErrorCode::LimitExceeded);
+    }
+
+    #[test]
+    fn bomb_exceeds_alloc() {
+        let opts = || options(None, None, Some(1024 * 1024));
+        assert_eq!(code(ImageLoader::load(&png_header(1024, 1024), None, opts())), ErrorCode::LimitExceeded);
+        assert_eq!(code(ImageLoader::load(&farbfeld_header(1024, 1024), None, opts())), ErrorCode::LimitExceeded);
+        assert_eq!(code(ImageLoader::load(&webp_header(1024, 1024), None, opts())), ErrorCode::LimitExceeded);
+    }
+
+    #[test]
+    fn within_limits() {
+        let buffer = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/__test__/_source/tree.png")).unwrap();
+        assert!(ImageLoader::load(&buffer, None, options(Some(157), Some(180), Some(1024 * 1024))).is_ok());
+        assert_eq!(code(ImageLoader::load(&buffer, None, options(Some(156), None, None))), ErrorCode::LimitExceeded);
+        assert_eq!(code(ImageLoader::load(&buffer, None, options(None, None, Some(-1)))), ErrorCode::InvalidParameter);
+    }
+
+    #[test]
+    fn auto_orient() {
+        let fixture = |n: u32| std::fs::read(format!("{}/__test__/_source/orientation/tree_{}.jpeg", env!("CARGO_MANIFEST_DIR"), n)).unwrap();
+        let oriented = || Some(LoadOptions { max_width: None, max_height: None, max_alloc: None, auto_orient: Some(true) });
+        let upright = ImageLoader::load(&fixture(1), None, None).unwrap().shared().to_rgba8();
+
+        for n in 1..=8 {
+            let stored = ImageLoader::load(&fixture(n), None, None).unwrap();
+            assert_eq!(stored.orientation(), n);
+
+            let image = ImageLoader::load(&fixture(n), None, oriented()).unwrap();
+            assert_eq!(image.orientation(), 1);
+            let pixels = image.shared().to_rgba8();
+            assert_eq!(pixels.dimensions(), upright.dimensions(), "orientation {}", n);
+
+            // the fixtures are re-encoded, so the pixels only match approximately
+            let diff: u64 = pixels.as_raw().iter().zip(upright.as_raw()).map(|(a, b)| a.abs_diff(*b) as u64).sum();
+            assert!(diff / (pixels.len() as u64) < 8, "orientation {} differs by {}", n, diff / pixels.len() as u64);
+        }
+    }
+
+    /// An animated GIF of 8x6 frames with the given delays in milliseconds
+    fn gif(delays: &[u32]) -> Vec<u8> {
+        use image::codecs::gif::GifEncoder;
+        use image::{Frame, RgbaImage};
+
+        let mut buf = vec![];
+        {
+            let mut encoder = GifEncoder::new(&mut buf);
+            for (i, delay) in delays.iter().enumerate() {
+                let canvas = RgbaImage::from_pixel(8, 6, image::Rgba([i as u8 * 60, 0, 0, 255]));
+                let frame = Frame::from_parts(canvas, 0, 0, Delay::from_numer_denom_ms(*delay, 1));
+                encoder.encode_frame(frame).unwrap();
+            }
+        }
+        buf
+    }
+
+    #[test]
+    fn gif_frames() {
+        let animation = ImageLoader::load_frames(&gif(&[100, 200, 50]), ImageFormat::Gif, None).unwrap();
+        assert_eq!(animation.frame_count(), 3);
+        assert_eq!(animation.dimensions(), vec![8, 6]);
+
+        let delays: Vec<u32> = (0..3).map(|i| animation.frame_info(i).unwrap().delay).collect();
+        assert_eq!(delays, vec![100, 200, 50]);
+        assert_eq!(animation.frame_info(1).unwrap().width, 8);
+        assert_eq!(animation.frame(2).unwrap().dimensions(), vec![8, 6]);
+
+        match animation.frame(3) {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => assert_eq!(err.status, ErrorCode::FrameOutOfBounds),
+        }
+    }
+
+    #[test]
+    fn frames_exceed_alloc() {
+        // every frame is 8 * 6 * 4 bytes, the third one does not fit
+        let res = ImageLoader::load_frames(&gif(&[10, 10, 10]), ImageFormat::Gif, options(None, None, Some(400)));
+        assert_eq!(res.err().map(|err| err.status), Some(ErrorCode::LimitExceeded));
+    }
+
+    #[test]
+    fn still_png_frames() {
+        let buffer = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/__test__/_source/tree.png")).unwrap();
+        let animation = ImageLoader::load_frames(&buffer, ImageFormat::Png, None).unwrap();
+        assert_eq!(animation.frame_count(), 1);
+        assert_eq!(animation.frame_info(0).unwrap().delay, 0);
+        assert_eq!(animation.frame_info(0).unwrap().disposal, "none");
+    }
+
+    #[test]
+    fn load_raw() {
+        let image = ImageLoader::load_raw(&[0, 64, 128, 255, 1, 2], 2, 1, "rgb8").unwrap();
+        assert_eq!(image.dimensions(), vec![2, 1]);
+        assert_eq!(image.color_type(), "rgb8");
+
+        let samples: Vec<u8> = [0.5f32, 1.0, 0.25].iter().flat_map(|f| f.to_ne_bytes()).collect();
+        let image = ImageLoader::load_raw(&samples, 1, 1, "rgb32f").unwrap();
+        assert_eq!(image.shared().to_rgba8().into_raw(), vec![128, 255, 64, 255]);
+
+        let samples: Vec<u8> = [257u16 * 128, 65535].iter().flat_map(|v| v.to_ne_bytes()).collect();
+        let image = ImageLoader::load_raw(&samples, 1, 1, "la16").unwrap();
+        assert_eq!(image.shared().to_rgba8().into_raw(), vec![128, 128, 128, 255]);
+
+        let err = |data: &[u8], w, h, color| ImageLoader::load_raw(data, w, h, color).err().unwrap();
+        assert_eq!(err(&[0; 5], 2, 1, "rgb8").status, ErrorCode::InvalidParameter);
+        assert_eq!(err(&[0; 7], 2, 1, "rgb8").status, ErrorCode::InvalidParameter);
+        assert_eq!(err(&[], 0, 1, "rgb8").status, ErrorCode::ZeroSize);
+        assert_eq!(err(&[0; 3], 1, 1, "rgb").status, ErrorCode::InvalidParameter);
+    }
+
+    #[test]
+    fn blank() {
+        let image = ImageLoader::blank(3, 2, "la16".into(), Some("#ffffff80".into())).unwrap();
+        assert_eq!(image.dimensions(), vec![3, 2]);
+        assert_eq!(image.color_type(), "la16");
+        assert_eq!(image.get_pixel(2, 1).unwrap(), vec![255, 255, 255, 128]);
+        assert_eq!(ImageLoader::blank(1, 1, "rgba8".into(), None).unwrap().get_pixel(0, 0).unwrap(), vec![0, 0, 0, 0]);
+
+        assert_eq!(code(ImageLoader::blank(1, 1, "rgba8".into(), Some("nope".into()))), ErrorCode::InvalidColor);
+        assert_eq!(code(ImageLoader::blank(0, 1, "rgba8".into(), None)), ErrorCode::ZeroSize);
+        assert_eq!(code(ImageLoader::blank(65535, 65535, "rgba8".into(), None)), ErrorCode::LimitExceeded);
     }
-}
\ No newline at end of file
+}
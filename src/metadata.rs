@@ -0,0 +1,977 @@
+use std::borrow::Cow;
+use std::io::{Cursor, Write};
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use image::ImageFormat;
+use napi::bindgen_prelude::{Buffer, Either};
+use crate::error::{error, ErrorCode, Result};
+use crate::probe::{riff_chunk, webp_chunks};
+
+const JPEG_XMP: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const JPEG_ICC: &[u8] = b"ICC_PROFILE\0";
+const JPEG_PHOTOSHOP: &[u8] = b"Photoshop 3.0\0";
+/// the keyword, followed by the compression flag and method of an uncompressed iTXt chunk
+const PNG_XMP: &[u8] = b"XML:com.adobe.xmp\0\0\0";
+
+/// Metadata captured from the source container, carried along with the pixels through the transforms
+#[derive(Clone, Default)]
+pub struct Metadata {
+    /// the raw EXIF block, starting with the TIFF header
+    pub exif: Option<Vec<u8>>,
+    /// the raw XMP packet
+    pub xmp: Option<Vec<u8>>,
+    /// the raw IPTC-IIM records
+    pub iptc: Option<Vec<u8>>,
+    /// the ICC color profile
+    pub icc: Option<Vec<u8>>,
+}
+
+impl Metadata {
+    /// Capture the metadata of an image in the given format, anything malformed is ignored
+    pub fn read(buffer: &[u8], format: ImageFormat) -> Self {
+        let blocks = match format {
+            ImageFormat::Jpeg => jpeg_blocks(buffer),
+            ImageFormat::Png => png_blocks(buffer),
+            ImageFormat::WebP => webp_blocks(buffer),
+            ImageFormat::Tiff => tiff_blocks(buffer),
+            _ => None,
+        };
+        let Some(blocks) = blocks else {
+            return Metadata::default();
+        };
+
+        Metadata {
+            exif: blocks.exif.filter(|exif| Tiff::new(exif).is_some()).map(Cow::into_owned),
+            xmp: blocks.xmp.map(|xmp| xmp.to_vec()),
+            iptc: blocks.iptc.map(|iptc| iptc.to_vec()),
+            icc: blocks.icc.map(Cow::into_owned),
+        }
+    }
+
+    /// Embed the kept metadata into an image encoded by the underlying library, formats without a known container are returned as is.
+    /// Fails with `TooLarge` if a block does not fit into its JPEG segments
+    pub fn embed(&self, encoded: Vec<u8>, format: ImageFormat, keep: Keep) -> Result<Vec<u8>> {
+        let mut exif = self.exif.clone().filter(|_| keep.exif);
+        if keep.strip_gps {
+            exif.iter_mut().for_each(|exif| strip_gps(exif));
+        }
+        let blocks = Blocks {
+            exif: exif.as_deref().map(Cow::Borrowed),
+            xmp: self.xmp.as_deref().filter(|_| keep.xmp),
+            iptc: self.iptc.as_deref().filter(|_| keep.iptc),
+            icc: self.icc.as_deref().filter(|_| keep.icc).map(Cow::Borrowed),
+        };
+
+        match format {
+            ImageFormat::Jpeg => jpeg_embed(encoded, &blocks),
+            ImageFormat::Png => Ok(png_embed(encoded, &blocks)),
+            ImageFormat::WebP => Ok(webp_embed(encoded, &blocks)),
+            _ => Ok(encoded),
+        }
+    }
+
+    /// Get the EXIF orientation within `1-8`, `1` if there is none
+    pub fn orientation(&self) -> u8 {
+        self.exif.as_deref().and_then(exif_orientation).map_or(1, |(value, _)| value)
+    }
+
+    /// Get a copy with the EXIF orientation reset to `1`, e.g. after the orientation has been applied to the pixels
+    pub fn upright(&self) -> Self {
+        let mut metadata = self.clone();
+        if let Some(exif) = metadata.exif.as_mut() {
+            if let Some((_, at)) = exif_orientation(exif) {
+                let one = if exif.starts_with(b"MM") { [0, 1] } else { [1, 0] };
+                exif[at..at + 2].copy_from_slice(&one);
+            }
+        }
+        metadata
+    }
+}
+
+/// The metadata blocks found in a container, borrowed from the buffer where possible
+#[derive(Default)]
+struct Blocks<'a> {
+    exif: Option<Cow<'a, [u8]>>,
+    xmp: Option<&'a [u8]>,
+    iptc: Option<&'a [u8]>,
+    icc: Option<Cow<'a, [u8]>>,
+}
+
+/// Walk the segments of a JPEG until the scan starts, calling `visit` with the marker and data of each segment
+fn jpeg_segments<'a>(buffer: &'a [u8], mut visit: impl FnMut(u8, &'a [u8])) -> Option<()> {
+    if buffer.get(0..2)? != [0xFF, 0xD8] {
+        return None;
+    }
+
+    let mut at = 2;
+    loop {
+        // markers may be preceded by any number of fill bytes
+        while *buffer.get(at)? == 0xFF && *buffer.get(at + 1)? == 0xFF {
+            at += 1;
+        }
+        if *buffer.get(at)? != 0xFF {
+            return None;
+        }
+        match *buffer.get(at + 1)? {
+            // start of scan or end of image, there is no metadata after this
+            0xDA | 0xD9 => return Some(()),
+            // standalone markers without a length
+            0x01 | 0xD0..=0xD7 => at += 2,
+            marker => {
+                let len = u16::from_be_bytes([*buffer.get(at + 2)?, *buffer.get(at + 3)?]) as usize;
+                visit(marker, buffer.get(at + 4..at + 2 + len)?);
+                at += 2 + len;
+            }
+        }
+    }
+}
+
+/// Walk the chunks of a PNG until the image ends, calling `visit` with the type and data of each chunk
+fn png_chunks<'a>(buffer: &'a [u8], mut visit: impl FnMut(&'a [u8], &'a [u8])) -> Option<()> {
+    if buffer.get(0..8)? != b"\x89PNG\r\n\x1a\n" {
+        return None;
+    }
+
+    let mut at = 8;
+    loop {
+        let len = u32::from_be_bytes(buffer.get(at..at + 4)?.try_into().ok()?) as usize;
+        let kind = buffer.get(at + 4..at + 8)?;
+        if kind == b"IEND" {
+            return Some(());
+        }
+        visit(kind, buffer.get(at + 8..at + 8 + len)?);
+        at += 12 + len;
+    }
+}
+
+/// Find the IPTC-IIM records among the image resource blocks of a Photoshop segment
+fn photoshop_iptc(data: &[u8]) -> Option<&[u8]> {
+    let mut at = 0;
+    while data.get(at..at + 4)? == b"8BIM" {
+        let id = u16::from_be_bytes([*data.get(at + 4)?, *data.get(at + 5)?]);
+        // the name is a pascal string padded to an even length
+        let name = (*data.get(at + 6)? as usize + 2) & !1;
+        let len_at = at + 6 + name;
+        let len = u32::from_be_bytes(data.get(len_at..len_at + 4)?.try_into().ok()?) as usize;
+        let resource = data.get(len_at + 4..len_at + 4 + len)?;
+        if id == 0x0404 {
+            return Some(resource);
+        }
+        at = len_at + 4 + ((len + 1) & !1);
+    }
+    None
+}
+
+fn jpeg_blocks(buffer: &[u8]) -> Option<Blocks<'_>> {
+    let mut blocks = Blocks::default();
+    let mut icc_chunks = vec![];
+    jpeg_segments(buffer, |marker, data| match marker {
+        0xE1 if blocks.exif.is_none() && data.starts_with(b"Exif\0\0") => blocks.exif = Some(Cow::Borrowed(&data[6..])),
+        0xE1 if blocks.xmp.is_none() => blocks.xmp = data.strip_prefix(JPEG_XMP),
+        // the profile is split into numbered chunks of up to 64 KiB
+        0xE2 if data.len() > 14 && data.starts_with(JPEG_ICC) => icc_chunks.push((data[12], &data[14..])),
+        0xED if blocks.iptc.is_none() => blocks.iptc = data.strip_prefix(JPEG_PHOTOSHOP).and_then(photoshop_iptc),
+        _ => {}
+    })?;
+
+    if !icc_chunks.is_empty() {
+        icc_chunks.sort_by_key(|(seq, _)| *seq);
+        blocks.icc = Some(Cow::Owned(icc_chunks.iter().flat_map(|(_, chunk)| chunk.iter().copied()).collect()));
+    }
+    Some(blocks)
+}
+
+fn png_blocks(buffer: &[u8]) -> Option<Blocks<'_>> {
+    let mut blocks = Blocks::default();
+    let mut has_icc = false;
+    png_chunks(buffer, |kind, data| match kind {
+        b"eXIf" => blocks.exif = Some(Cow::Borrowed(data)),
+        b"iCCP" => has_icc = true,
+        // keyword, compression flag and method, language and translated keyword, only uncompressed text is read
+        b"iTXt" if data.starts_with(PNG_XMP) => {
+            let text = &data[PNG_XMP.len()..];
+            blocks.xmp = text.iter().position(|b| *b == 0)
+                .and_then(|lang| text[lang + 1..].iter().position(|b| *b == 0).map(|keyword| &text[lang + keyword + 2..]));
+        }
+        _ => {}
+    })?;
+
+    // the profile is compressed, let the png decoder inflate it
+    if has_icc {
+        let reader = png::Decoder::new(Cursor::new(buffer)).read_info().ok();
+        blocks.icc = reader.and_then(|reader| reader.info().icc_profile.as_ref().map(|icc| Cow::Owned(icc.to_vec())));
+    }
+    Some(blocks)
+}
+
+fn webp_blocks(buffer: &[u8]) -> Option<Blocks<'_>> {
+    let mut blocks = Blocks::default();
+    webp_chunks(buffer, |fourcc, data| match fourcc {
+        // some writers keep the jpeg style prefix
+        b"EXIF" => blocks.exif = Some(Cow::Borrowed(data.strip_prefix(b"Exif\0\0").unwrap_or(data))),
+        b"XMP " => blocks.xmp = Some(data),
+        b"ICCP" => blocks.icc = Some(Cow::Borrowed(data)),
+        _ => {}
+    })?;
+    Some(blocks)
+}
+
+fn tiff_blocks(buffer: &[u8]) -> Option<Blocks<'_>> {
+    // the whole file is the tiff structure, with the other blocks as tags of the first IFD
+    let tiff = Tiff::new(buffer)?;
+    let ifd0 = tiff.first_ifd()?;
+    let tag = |tag| tiff.entry(ifd0, tag).and_then(|entry| tiff.bytes(entry));
+
+    Some(Blocks {
+        exif: tiff_exif(&tiff, ifd0).map(Cow::Owned),
+        xmp: tag(0x02BC),
+        iptc: tag(0x83BB),
+        icc: tag(0x8773).map(Cow::Borrowed),
+    })
+}
+
+/// Tags of the first IFD of a TIFF file that describe the pixel data, or hold the XMP, IPTC and ICC blocks
+const TIFF_IMAGE_TAGS: &[u16] = &[
+    0x00FE, 0x00FF, 0x0100, 0x0101, 0x0102, 0x0103, 0x0106, 0x0107, 0x010A, 0x0111, 0x0115, 0x0116, 0x0117, 0x0118, 0x0119,
+    0x011C, 0x013D, 0x0140, 0x0142, 0x0143, 0x0144, 0x0145, 0x014A, 0x0152, 0x0153, 0x015B, 0x0201, 0x0202, 0x02BC, 0x83BB, 0x8773,
+];
+
+/// Build an EXIF block from the first IFD of a TIFF file, keeping the metadata tags with the EXIF and GPS IFDs but not the pixel data.
+/// Returns `None` if there are no metadata tags
+fn tiff_exif(tiff: &Tiff, ifd0: usize) -> Option<Vec<u8>> {
+    let mut exif = tiff.data[..4].to_vec();
+    exif.extend_from_slice(&tiff.u32_bytes(8));
+    tiff.copy_ifd(ifd0, &mut exif, 0)?;
+    Some(exif).filter(|exif| tiff.u16_bytes(0) != exif[8..10])
+}
+
+/// A reader for the TIFF structure of an EXIF block
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+/// An entry of an IFD, `at` is the offset of its value, either inline or pointed to
+#[derive(Clone, Copy)]
+struct Entry {
+    kind: u16,
+    count: u32,
+    at: usize,
+}
+
+impl Entry {
+    /// Get the size of the value in bytes
+    fn len(&self) -> usize {
+        let size = match self.kind {
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 => 8,
+            _ => 1,
+        };
+        self.count as usize * size
+    }
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        match data.get(0..4)? {
+            b"II*\0" => Some(Tiff { data, big_endian: false }),
+            b"MM\0*" => Some(Tiff { data, big_endian: true }),
+            _ => None,
+        }
+    }
+
+    fn u16(&self, at: usize) -> Option<u16> {
+        let b = self.data.get(at..at + 2)?;
+        Some(if self.big_endian { u16::from_be_bytes([b[0], b[1]]) } else { u16::from_le_bytes([b[0], b[1]]) })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let b = self.data.get(at..at + 4)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Some(if self.big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) })
+    }
+
+    fn u16_bytes(&self, value: u16) -> [u8; 2] {
+        if self.big_endian { value.to_be_bytes() } else { value.to_le_bytes() }
+    }
+
+    fn u32_bytes(&self, value: u32) -> [u8; 4] {
+        if self.big_endian { value.to_be_bytes() } else { value.to_le_bytes() }
+    }
+
+    /// Copy the IFD at `ifd` to the end of `out` with the values and sub IFDs it points to, in the same byte order.
+    /// `depth` is `0` for the first IFD of a TIFF file, whose image tags are left out. Returns the offset of the copy
+    fn copy_ifd(&self, ifd: usize, out: &mut Vec<u8>, depth: u8) -> Option<u32> {
+        // the EXIF and GPS IFDs of the first IFD, and the interoperability IFD of the EXIF IFD
+        let is_pointer = |tag| matches!((depth, tag), (0, 0x8769 | 0x8825) | (1, 0xA005));
+        let entries: Vec<(usize, u16, Entry)> = (0..self.u16(ifd)? as usize)
+            .map(|i| ifd + 2 + i * 12)
+            .filter_map(|at| Some((at, self.u16(at)?, self.entry_at(at)?)))
+            .filter(|(_, tag, entry)| (depth > 0 || !TIFF_IMAGE_TAGS.contains(tag)) && self.bytes(*entry).is_some())
+            .collect();
+
+        // offsets are word aligned
+        out.resize(out.len() + out.len() % 2, 0);
+        let start = out.len();
+        out.extend_from_slice(&self.u16_bytes(entries.len() as u16));
+        entries.iter().for_each(|(at, _, _)| out.extend_from_slice(&self.data[*at..*at + 12]));
+        // no next IFD
+        out.extend_from_slice(&[0; 4]);
+
+        for (i, (_, tag, entry)) in entries.iter().enumerate() {
+            let value = if is_pointer(*tag) {
+                self.copy_ifd(self.u32(entry.at)? as usize, out, depth + 1)?
+            } else if entry.len() > 4 {
+                out.resize(out.len() + out.len() % 2, 0);
+                let at = out.len() as u32;
+                out.extend_from_slice(self.bytes(*entry)?);
+                at
+            } else {
+                continue;
+            };
+            let field = start + 2 + i * 12 + 8;
+            out[field..field + 4].copy_from_slice(&self.u32_bytes(value));
+        }
+        u32::try_from(start).ok()
+    }
+
+    /// Get the offset of the first IFD
+    fn first_ifd(&self) -> Option<usize> {
+        self.u32(4).map(|at| at as usize)
+    }
+
+    /// Find the entry of a tag in the IFD at `ifd`
+    fn entry(&self, ifd: usize, tag: u16) -> Option<Entry> {
+        let count = self.u16(ifd)? as usize;
+        let at = (0..count).map(|i| ifd + 2 + i * 12).find(|&at| self.u16(at) == Some(tag))?;
+        self.entry_at(at)
+    }
+
+    /// Read the entry at `at`
+    fn entry_at(&self, at: usize) -> Option<Entry> {
+        let kind = self.u16(at + 2)?;
+        let count = self.u32(at + 4)?;
+        let entry = Entry { kind, count, at: at + 8 };
+        // values of up to 4 bytes are stored inline
+        let value = if entry.len() <= 4 { at + 8 } else { self.u32(at + 8)? as usize };
+        Some(Entry { at: value, ..entry })
+    }
+
+    /// Get the offset of the IFD an entry points to, e.g. the EXIF or GPS IFD
+    fn sub_ifd(&self, ifd: usize, tag: u16) -> Option<usize> {
+        self.uint(self.entry(ifd, tag)?).map(|at| at as usize)
+    }
+
+    fn bytes(&self, entry: Entry) -> Option<&'a [u8]> {
+        // the iptc tag is often declared as LONG
+        self.data.get(entry.at..entry.at + entry.len())
+    }
+
+    fn ascii(&self, entry: Entry) -> Option<String> {
+        let text = String::from_utf8_lossy(self.bytes(entry)?);
+        let text = text.trim_end_matches('\0').trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    /// Get the first value of a BYTE, SHORT or LONG entry
+    fn uint(&self, entry: Entry) -> Option<u32> {
+        match entry.kind {
+            1 | 7 => self.data.get(entry.at).map(|b| *b as u32),
+            3 => self.u16(entry.at).map(|v| v as u32),
+            4 => self.u32(entry.at),
+            _ => None,
+        }
+    }
+
+    /// Get the value at `index` of a RATIONAL or SRATIONAL entry
+    fn rational(&self, entry: Entry, index: u32) -> Option<f64> {
+        if index >= entry.count {
+            return None;
+        }
+        let at = entry.at + index as usize * 8;
+        let (numer, denom) = match entry.kind {
+            5 => (self.u32(at)? as f64, self.u32(at + 4)? as f64),
+            10 => (self.u32(at)? as i32 as f64, self.u32(at + 4)? as i32 as f64),
+            _ => return None,
+        };
+        (denom != 0.0).then(|| numer / denom)
+    }
+}
+
+/// Find the orientation tag in the first IFD of an EXIF block, returns the value and the offset of the value
+fn exif_orientation(exif: &[u8]) -> Option<(u8, usize)> {
+    let tiff = Tiff::new(exif)?;
+    let entry = tiff.entry(tiff.first_ifd()?, 0x0112)?;
+    match tiff.uint(entry)? {
+        value @ 1..=8 if entry.kind == 3 => Some((value as u8, entry.at)),
+        _ => None,
+    }
+}
+
+/// The GPS position recorded in the EXIF data
+#[napi(object)]
+pub struct GpsInfo {
+    /// Latitude in degrees, negative for south
+    pub latitude: f64,
+    /// Longitude in degrees, negative for west
+    pub longitude: f64,
+    /// Altitude in meters, negative for below sea level
+    pub altitude: Option<f64>,
+}
+
+/// The commonly used EXIF tags, absent tags are `undefined`
+#[napi(object)]
+pub struct ExifInfo {
+    /// The manufacturer of the camera
+    pub make: Option<String>,
+    /// The model of the camera
+    pub model: Option<String>,
+    /// The lens model
+    pub lens_model: Option<String>,
+    /// The software used to create the image
+    pub software: Option<String>,
+    /// The person who created the image
+    pub artist: Option<String>,
+    /// The copyright notice
+    pub copyright: Option<String>,
+    /// The date and time the file was changed, in the format of `YYYY:MM:DD HH:MM:SS`
+    pub date_time: Option<String>,
+    /// The date and time the image was captured, in the format of `YYYY:MM:DD HH:MM:SS`
+    pub date_time_original: Option<String>,
+    /// The orientation within `1-8`
+    pub orientation: Option<u32>,
+    /// The exposure time, in seconds
+    pub exposure_time: Option<f64>,
+    /// The f-number of the aperture
+    pub f_number: Option<f64>,
+    /// The ISO speed
+    pub iso: Option<u32>,
+    /// The focal length of the lens, in millimeters
+    pub focal_length: Option<f64>,
+    /// The GPS position
+    pub gps: Option<GpsInfo>,
+}
+
+/// The metadata of an image as captured from the source, absent blocks are `undefined`
+#[napi(object)]
+pub struct ImageMetadata {
+    /// The commonly used EXIF tags
+    pub exif: Option<ExifInfo>,
+    /// The raw XMP packet, an XML document
+    pub xmp: Option<String>,
+    /// The raw IPTC-IIM records
+    pub iptc: Option<Buffer>,
+}
+
+/// Parse the commonly used tags of an EXIF block
+pub fn exif_info(exif: &[u8]) -> Option<ExifInfo> {
+    let tiff = Tiff::new(exif)?;
+    let ifd0 = tiff.first_ifd()?;
+    let exif_ifd = tiff.sub_ifd(ifd0, 0x8769);
+    let gps_ifd = tiff.sub_ifd(ifd0, 0x8825);
+
+    let ascii = |ifd: Option<usize>, tag| ifd.and_then(|ifd| tiff.entry(ifd, tag)).and_then(|entry| tiff.ascii(entry));
+    let rational = |ifd: Option<usize>, tag, index| ifd.and_then(|ifd| tiff.entry(ifd, tag)).and_then(|entry| tiff.rational(entry, index));
+    let uint = |ifd: Option<usize>, tag| ifd.and_then(|ifd| tiff.entry(ifd, tag)).and_then(|entry| tiff.uint(entry));
+
+    // degrees, minutes and seconds, negated for the south or west reference
+    let coordinate = |reference: u16, value: u16, negative: &str| {
+        let degrees = rational(gps_ifd, value, 0)? + rational(gps_ifd, value, 1).unwrap_or(0.0) / 60.0 + rational(gps_ifd, value, 2).unwrap_or(0.0) / 3600.0;
+        Some(if ascii(gps_ifd, reference).as_deref() == Some(negative) { -degrees } else { degrees })
+    };
+    let gps = match (coordinate(0x0001, 0x0002, "S"), coordinate(0x0003, 0x0004, "W")) {
+        (Some(latitude), Some(longitude)) => Some(GpsInfo {
+            latitude,
+            longitude,
+            altitude: rational(gps_ifd, 0x0006, 0).map(|altitude| if uint(gps_ifd, 0x0005) == Some(1) { -altitude } else { altitude }),
+        }),
+        _ => None,
+    };
+
+    Some(ExifInfo {
+        make: ascii(Some(ifd0), 0x010F),
+        model: ascii(Some(ifd0), 0x0110),
+        lens_model: ascii(exif_ifd, 0xA434),
+        software: ascii(Some(ifd0), 0x0131),
+        artist: ascii(Some(ifd0), 0x013B),
+        copyright: ascii(Some(ifd0), 0x8298),
+        date_time: ascii(Some(ifd0), 0x0132),
+        date_time_original: ascii(exif_ifd, 0x9003),
+        orientation: uint(Some(ifd0), 0x0112),
+        exposure_time: rational(exif_ifd, 0x829A, 0),
+        f_number: rational(exif_ifd, 0x829D, 0),
+        iso: uint(exif_ifd, 0x8827),
+        focal_length: rational(exif_ifd, 0x920A, 0),
+        gps,
+    })
+}
+
+/// The metadata blocks to keep when encoding
+#[derive(Clone, Copy, Default)]
+pub struct Keep {
+    pub exif: bool,
+    pub xmp: bool,
+    pub iptc: bool,
+    pub icc: bool,
+    pub strip_gps: bool,
+}
+
+impl Keep {
+    /// Parse the `keepMetadata` option, nothing is kept when absent
+    pub fn parse(keep: Option<Either<String, Vec<String>>>, strip_gps: Option<bool>) -> Result<Self> {
+        let names = match keep {
+            None => vec![],
+            Some(Either::A(preset)) => match preset.as_str() {
+                "none" => vec![],
+                "icc" => vec![preset],
+                "all" => ["exif", "xmp", "iptc", "icc"].map(String::from).to_vec(),
+                _ => return Err(error(ErrorCode::InvalidParameter, "Invalid parameter", format!("keepMetadata {}, expected 'none', 'icc', 'all' or an array", preset))),
+            },
+            Some(Either::B(names)) => names,
+        };
+
+        let mut keep = Keep { strip_gps: strip_gps.unwrap_or(false), ..Default::default() };
+        for name in names {
+            match name.as_str() {
+                "exif" => keep.exif = true,
+                "xmp" => keep.xmp = true,
+                "iptc" => keep.iptc = true,
+                "icc" => keep.icc = true,
+                _ => return Err(error(ErrorCode::InvalidParameter, "Invalid parameter", format!("keepMetadata {}, expected 'exif', 'xmp', 'iptc' or 'icc'", name))),
+            }
+        }
+        Ok(keep)
+    }
+}
+
+/// Remove the GPS position from an EXIF block in place, leaving an empty GPS IFD behind
+fn strip_gps(exif: &mut [u8]) {
+    let Some(tiff) = Tiff::new(exif) else { return };
+    let Some(gps) = tiff.first_ifd().and_then(|ifd0| tiff.sub_ifd(ifd0, 0x8825)) else { return };
+    let Some(count) = tiff.u16(gps) else { return };
+
+    // values stored outside of the entries, e.g. the coordinates
+    let pointed: Vec<(usize, usize)> = (0..count as usize)
+        .filter_map(|i| {
+            let entry = tiff.entry_at(gps + 2 + i * 12)?;
+            (entry.at != gps + 2 + i * 12 + 8).then(|| (entry.at, entry.len()))
+        })
+        .collect();
+
+    let end = (gps + 2 + count as usize * 12 + 4).min(exif.len());
+    for (at, len) in pointed {
+        let end = (at + len).min(exif.len());
+        exif.get_mut(at..end).into_iter().for_each(|value| value.fill(0));
+    }
+    // no entries, and no next IFD
+    exif.get_mut(gps..end).into_iter().for_each(|ifd| ifd.fill(0));
+}
+
+/// Insert segments after the SOI and JFIF segments written by the underlying encoder.
+/// The ICC profile is split into up to 255 segments, EXIF, XMP and IPTC must fit into a single one
+fn jpeg_embed(encoded: Vec<u8>, blocks: &Blocks) -> Result<Vec<u8>> {
+    let mut segments = vec![];
+    let mut segment = |marker: u8, name: &str, parts: &[&[u8]]| {
+        let len: usize = parts.iter().map(|part| part.len()).sum();
+        // segments are limited to 64 KiB, including the length itself
+        if len + 2 > u16::MAX as usize {
+            return Err(error(ErrorCode::TooLarge, "Metadata too large", format!("{} of {} bytes exceeds a jpeg segment", name, len)));
+        }
+        segments.extend_from_slice(&[0xFF, marker]);
+        segments.extend_from_slice(&(len as u16 + 2).to_be_bytes());
+        parts.iter().for_each(|part| segments.extend_from_slice(part));
+        Ok(())
+    };
+
+    if let Some(exif) = blocks.exif.as_deref() {
+        segment(0xE1, "exif", &[b"Exif\0\0", exif])?;
+    }
+    if let Some(xmp) = blocks.xmp {
+        segment(0xE1, "xmp", &[JPEG_XMP, xmp])?;
+    }
+    if let Some(icc) = blocks.icc.as_deref() {
+        let chunks: Vec<&[u8]> = icc.chunks(u16::MAX as usize - 2 - JPEG_ICC.len() - 2).collect();
+        // the sequence number and the count of the chunks are single bytes
+        let count = u8::try_from(chunks.len()).map_err(|_| {
+            error(ErrorCode::TooLarge, "Metadata too large", format!("icc of {} bytes exceeds 255 jpeg segments", icc.len()))
+        })?;
+        for (seq, chunk) in (1..=count).zip(chunks) {
+            segment(0xE2, "icc", &[JPEG_ICC, &[seq, count], chunk])?;
+        }
+    }
+    if let Some(iptc) = blocks.iptc {
+        // a single image resource block with an empty name
+        let pad: &[u8] = if iptc.len() % 2 == 1 { &[0] } else { &[] };
+        segment(0xED, "iptc", &[JPEG_PHOTOSHOP, b"8BIM\x04\x04\0\0", &(iptc.len() as u32).to_be_bytes(), iptc, pad])?;
+    }
+
+    let mut at = 2;
+    if encoded.get(2..4) == Some(&[0xFF, 0xE0]) {
+        at += 2 + u16::from_be_bytes([encoded[4], encoded[5]]) as usize;
+    }
+    Ok([&encoded[..at], &segments, &encoded[at..]].concat())
+}
+
+/// Insert chunks after the IHDR chunk written by the underlying encoder, PNG has no place for IPTC
+fn png_embed(encoded: Vec<u8>, blocks: &Blocks) -> Vec<u8> {
+    let mut chunks = vec![];
+    let mut chunk = |kind: &[u8], parts: &[&[u8]]| {
+        let data = parts.concat();
+        let mut crc = Crc::new();
+        crc.update(kind);
+        crc.update(&data);
+
+        chunks.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        chunks.extend_from_slice(kind);
+        chunks.extend_from_slice(&data);
+        chunks.extend_from_slice(&crc.sum().to_be_bytes());
+    };
+
+    if let Some(icc) = blocks.icc.as_deref() {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        if let Ok(compressed) = encoder.write_all(icc).and_then(|_| encoder.finish()) {
+            // profile name and compression method
+            chunk(b"iCCP", &[b"icc\0\0", &compressed]);
+        }
+    }
+    if let Some(exif) = blocks.exif.as_deref() {
+        chunk(b"eXIf", &[exif]);
+    }
+    if let Some(xmp) = blocks.xmp {
+        // empty language and translated keyword
+        chunk(b"iTXt", &[PNG_XMP, b"\0\0", xmp]);
+    }
+
+    // signature and IHDR
+    let at = 8 + 25;
+    [&encoded[..at], &chunks, &encoded[at..]].concat()
+}
+
+/// Wrap the VP8L chunk written by the underlying encoder in an extended WebP with the ICCP, EXIF and XMP chunks
+fn webp_embed(encoded: Vec<u8>, blocks: &Blocks) -> Vec<u8> {
+    if blocks.exif.is_none() && blocks.xmp.is_none() && blocks.icc.is_none() {
+        return encoded;
+    }
+    // the signature (1 byte) of the lossless bitstream, then 14-bit width - 1, 14-bit height - 1 and the alpha bit
+    let bits = match encoded.get(12..16) == Some(b"VP8L") {
+        true => match encoded.get(21..25) {
+            Some(b) => u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            None => return encoded,
+        },
+        false => return encoded,
+    };
+
+    let flags = [(blocks.icc.is_some(), 0x20), (bits >> 28 & 1 == 1, 0x10), (blocks.exif.is_some(), 0x08), (blocks.xmp.is_some(), 0x04)]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0u8, |flags, (_, flag)| flags | flag);
+    let mut vp8x = vec![flags, 0, 0, 0];
+    vp8x.extend_from_slice(&(bits & 0x3FFF).to_le_bytes()[..3]);
+    vp8x.extend_from_slice(&((bits >> 14) & 0x3FFF).to_le_bytes()[..3]);
+
+    let mut chunks = vec![];
+    riff_chunk(&mut chunks, b"VP8X", &vp8x);
+    if let Some(icc) = blocks.icc.as_deref() {
+        riff_chunk(&mut chunks, b"ICCP", icc);
+    }
+    chunks.extend_from_slice(&encoded[12..]);
+    if let Some(exif) = blocks.exif.as_deref() {
+        riff_chunk(&mut chunks, b"EXIF", exif);
+    }
+    if let Some(xmp) = blocks.xmp {
+        riff_chunk(&mut chunks, b"XMP ", xmp);
+    }
+
+    let mut buf = b"RIFF".to_vec();
+    buf.extend_from_slice(&(chunks.len() as u32 + 4).to_le_bytes());
+    buf.extend_from_slice(b"WEBP");
+    buf.extend_from_slice(&chunks);
+    buf
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+
+    type Field = (u16, u16, u32, Vec<u8>);
+
+    /// Build an EXIF block from IFD0, the EXIF IFD and the GPS IFD, the sub IFD pointers are added to IFD0
+    fn tiff(ifd0: &[Field], exif_ifd: &[Field], gps_ifd: &[Field], big_endian: bool) -> Vec<u8> {
+        let u16_bytes = |v: u16| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+        let u32_bytes = |v: u32| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+        let size = |fields: &[Field]| 6 + fields.len() * 12 + fields.iter().map(|f| if f.3.len() > 4 { f.3.len() } else { 0 }).sum::<usize>();
+
+        let mut ifd0 = ifd0.to_vec();
+        let exif_at = 8 + size(&ifd0) + 24;
+        ifd0.push((0x8769, 4, 1, u32_bytes(exif_at as u32).to_vec()));
+        ifd0.push((0x8825, 4, 1, u32_bytes((exif_at + size(exif_ifd)) as u32).to_vec()));
+
+        let mut buf = if big_endian { b"MM\0*".to_vec() } else { b"II*\0".to_vec() };
+        buf.extend_from_slice(&u32_bytes(8));
+        for fields in [&ifd0[..], exif_ifd, gps_ifd] {
+            let mut data_at = buf.len() + 6 + fields.len() * 12;
+            let mut data = vec![];
+            buf.extend_from_slice(&u16_bytes(fields.len() as u16));
+            for (tag, kind, count, value) in fields {
+                buf.extend_from_slice(&u16_bytes(*tag));
+                buf.extend_from_slice(&u16_bytes(*kind));
+                buf.extend_from_slice(&u32_bytes(*count));
+                if value.len() > 4 {
+                    buf.extend_from_slice(&u32_bytes(data_at as u32));
+                    data_at += value.len();
+                    data.extend_from_slice(value);
+                } else {
+                    buf.extend_from_slice(value);
+                    buf.resize(buf.len() + 4 - value.len(), 0);
+                }
+            }
+            buf.extend_from_slice(&u32_bytes(0));
+            buf.extend_from_slice(&data);
+        }
+        buf
+    }
+
+    fn ascii(tag: u16, text: &str) -> Field {
+        (tag, 2, text.len() as u32 + 1, [text.as_bytes(), b"\0"].concat())
+    }
+
+    fn short(tag: u16, value: u16, big_endian: bool) -> Field {
+        (tag, 3, 1, if big_endian { value.to_be_bytes() } else { value.to_le_bytes() }.to_vec())
+    }
+
+    fn rationals(tag: u16, values: &[(u32, u32)], big_endian: bool) -> Field {
+        let bytes = |v: u32| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+        (tag, 5, values.len() as u32, values.iter().flat_map(|(n, d)| [bytes(*n), bytes(*d)].concat()).collect())
+    }
+
+    fn exif(orientation: u16, big_endian: bool) -> Vec<u8> {
+        tiff(&[short(0x0112, orientation, big_endian)], &[], &[], big_endian)
+    }
+
+    #[test]
+    fn orientation() {
+        for big_endian in [false, true] {
+            for value in 1..=8 {
+                let metadata = Metadata { exif: Some(exif(value, big_endian)), ..Default::default() };
+                assert_eq!(metadata.orientation(), value as u8);
+                assert_eq!(metadata.upright().orientation(), 1);
+            }
+            assert_eq!(Metadata { exif: Some(exif(9, big_endian)), ..Default::default() }.orientation(), 1);
+        }
+        assert_eq!(Metadata::default().orientation(), 1);
+    }
+
+    #[test]
+    fn exif_tags() {
+        for big_endian in [false, true] {
+            let block = tiff(
+                &[ascii(0x010F, "Fastigiata"), ascii(0x0110, "Tree 1"), short(0x0112, 6, big_endian), ascii(0x8298, "(c) someone")],
+                &[ascii(0x9003, "2024:05:01 12:30:00"), short(0x8827, 200, big_endian), rationals(0x829D, &[(28, 10)], big_endian)],
+                &[
+                    ascii(0x0001, "S"), rationals(0x0002, &[(33, 1), (51, 1), (5400, 100)], big_endian),
+                    ascii(0x0003, "E"), rationals(0x0004, &[(151, 1), (12, 1), (36, 1)], big_endian),
+                    (0x0005, 1, 1, vec![1]), rationals(0x0006, &[(25, 10)], big_endian),
+                ],
+                big_endian,
+            );
+            let info = exif_info(&block).unwrap();
+
+            assert_eq!(info.make.as_deref(), Some("Fastigiata"));
+            assert_eq!(info.model.as_deref(), Some("Tree 1"));
+            assert_eq!(info.copyright.as_deref(), Some("(c) someone"));
+            assert_eq!(info.orientation, Some(6));
+            assert_eq!(info.date_time_original.as_deref(), Some("2024:05:01 12:30:00"));
+            assert_eq!(info.iso, Some(200));
+            assert_eq!(info.f_number, Some(2.8));
+            assert_eq!(info.software, None);
+
+            let gps = info.gps.unwrap();
+            assert!((gps.latitude + 33.865).abs() < 1e-9);
+            assert!((gps.longitude - 151.21).abs() < 1e-9);
+            assert_eq!(gps.altitude, Some(-2.5));
+        }
+    }
+
+    #[test]
+    fn jpeg_blocks() {
+        let segment = |marker: u8, payload: &[u8]| [&[0xFF, marker][..], &(payload.len() as u16 + 2).to_be_bytes(), payload].concat();
+        let iptc = b"\x1c\x02\x74\x00\x04mine";
+        let photoshop = [&b"Photoshop 3.0\08BIM\x04\x04\x00\x00"[..], &(iptc.len() as u32).to_be_bytes(), iptc, b"\0"].concat();
+
+        let jpeg = [
+            &[0xFF, 0xD8][..],
+            &segment(0xE0, b"JFIF\0"),
+            &segment(0xE1, &[b"Exif\0\0".as_slice(), &exif(6, true)].concat()),
+            &segment(0xE1, b"http://ns.adobe.com/xap/1.0/\0<x:xmpmeta/>"),
+            &segment(0xED, &photoshop),
+            &[0xFF, 0xDA],
+        ].concat();
+
+        let metadata = Metadata::read(&jpeg, ImageFormat::Jpeg);
+        assert_eq!(metadata.orientation(), 6);
+        assert_eq!(metadata.xmp.as_deref(), Some(b"<x:xmpmeta/>".as_slice()));
+        assert_eq!(metadata.iptc.as_deref(), Some(iptc.as_slice()));
+
+        assert!(Metadata::read(&jpeg[..20], ImageFormat::Jpeg).exif.is_none());
+    }
+
+    #[test]
+    fn png_blocks() {
+        let chunk = |kind: &[u8], data: &[u8]| [&(data.len() as u32).to_be_bytes()[..], kind, data, &[0; 4]].concat();
+        let png = [
+            &b"\x89PNG\r\n\x1a\n"[..],
+            &chunk(b"IHDR", &[0; 13]),
+            &chunk(b"eXIf", &exif(3, false)),
+            &chunk(b"iTXt", b"XML:com.adobe.xmp\0\0\0\0\0<x:xmpmeta/>"),
+            &chunk(b"IEND", &[]),
+        ].concat();
+
+        let metadata = Metadata::read(&png, ImageFormat::Png);
+        assert_eq!(metadata.orientation(), 3);
+        assert_eq!(metadata.xmp.as_deref(), Some(b"<x:xmpmeta/>".as_slice()));
+        assert!(metadata.iptc.is_none());
+    }
+
+    fn photo() -> Metadata {
+        let exif = tiff(
+            &[ascii(0x010F, "Fastigiata"), ascii(0x8298, "(c) someone")],
+            &[],
+            &[ascii(0x0001, "N"), rationals(0x0002, &[(48, 1), (51, 1), (24, 1)], false), ascii(0x0003, "E"), rationals(0x0004, &[(2, 1), (21, 1), (7, 1)], false)],
+            false,
+        );
+        Metadata {
+            exif: Some(exif),
+            xmp: Some(b"<x:xmpmeta/>".to_vec()),
+            iptc: Some(b"\x1c\x02\x74\x00\x04mine".to_vec()),
+            icc: Some((0..70000).map(|i| (i % 251) as u8).collect()),
+        }
+    }
+
+    fn encoded(format: image::ImageOutputFormat) -> Vec<u8> {
+        let mut buf = Cursor::new(vec![]);
+        image::DynamicImage::new_rgb8(4, 4).write_to(&mut buf, format).unwrap();
+        buf.into_inner()
+    }
+
+    #[test]
+    fn keep_parse() {
+        let keep = |value| Keep::parse(Some(value), None);
+        assert!(!Keep::parse(None, None).unwrap().exif);
+        assert!(keep(Either::A("icc".into())).unwrap().icc && !keep(Either::A("icc".into())).unwrap().exif);
+
+        let all = keep(Either::A("all".into())).unwrap();
+        assert!(all.exif && all.xmp && all.iptc && all.icc && !all.strip_gps);
+
+        let listed = keep(Either::B(vec!["exif".into(), "xmp".into()])).unwrap();
+        assert!(listed.exif && listed.xmp && !listed.iptc && !listed.icc);
+
+        assert_eq!(keep(Either::A("some".into())).err().map(|err| err.status), Some(ErrorCode::InvalidParameter));
+        assert_eq!(keep(Either::B(vec!["gps".into()])).err().map(|err| err.status), Some(ErrorCode::InvalidParameter));
+    }
+
+    #[test]
+    fn embed_roundtrip() {
+        let source = photo();
+        let all = Keep { exif: true, xmp: true, iptc: true, icc: true, strip_gps: false };
+
+        let jpeg = source.embed(encoded(image::ImageOutputFormat::Jpeg(80)), ImageFormat::Jpeg, all).unwrap();
+        let read = Metadata::read(&jpeg, ImageFormat::Jpeg);
+        assert_eq!(read.exif, source.exif);
+        assert_eq!(read.xmp, source.xmp);
+        assert_eq!(read.iptc, source.iptc);
+        assert_eq!(read.icc, source.icc);
+        assert!(image::load_from_memory(&jpeg).is_ok());
+
+        let png = source.embed(encoded(image::ImageOutputFormat::Png), ImageFormat::Png, all).unwrap();
+        let read = Metadata::read(&png, ImageFormat::Png);
+        assert_eq!(read.exif, source.exif);
+        assert_eq!(read.xmp, source.xmp);
+        assert_eq!(read.icc, source.icc);
+        assert!(read.iptc.is_none());
+        assert!(image::load_from_memory(&png).is_ok());
+
+        let mut lossless = vec![];
+        image::codecs::webp::WebPEncoder::new_lossless(&mut lossless).encode(&[0; 48], 4, 4, image::ColorType::Rgb8).unwrap();
+        let webp = source.embed(lossless.clone(), ImageFormat::WebP, all).unwrap();
+        let read = Metadata::read(&webp, ImageFormat::WebP);
+        assert_eq!(read.exif, source.exif);
+        assert_eq!(read.xmp, source.xmp);
+        assert_eq!(read.icc, source.icc);
+        assert!(read.iptc.is_none());
+        assert_eq!(image::load_from_memory(&webp).unwrap().to_rgb8(), image::load_from_memory(&lossless).unwrap().to_rgb8());
+        assert_eq!(source.embed(lossless.clone(), ImageFormat::WebP, Keep::default()).unwrap(), lossless);
+
+        let none = source.embed(encoded(image::ImageOutputFormat::Png), ImageFormat::Png, Keep::default()).unwrap();
+        assert_eq!(none, encoded(image::ImageOutputFormat::Png));
+    }
+
+    #[test]
+    fn strip_gps_only() {
+        let source = photo();
+        assert!(exif_info(source.exif.as_ref().unwrap()).unwrap().gps.is_some());
+
+        let keep = Keep { exif: true, strip_gps: true, ..Default::default() };
+        let jpeg = source.embed(encoded(image::ImageOutputFormat::Jpeg(80)), ImageFormat::Jpeg, keep).unwrap();
+        let exif = Metadata::read(&jpeg, ImageFormat::Jpeg).exif.unwrap();
+        let info = exif_info(&exif).unwrap();
+
+        assert!(info.gps.is_none());
+        assert_eq!(info.make.as_deref(), Some("Fastigiata"));
+        assert_eq!(info.copyright.as_deref(), Some("(c) someone"));
+        // the coordinates are wiped, not just unlinked
+        assert!(exif.windows(8).all(|w| w != [0, 0, 0, 48, 0, 0, 0, 1] && w != [48, 0, 0, 0, 1, 0, 0, 0]));
+    }
+
+    #[test]
+    fn tiff_exif() {
+        for big_endian in [false, true] {
+            let u32_bytes = |v: u32| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() }.to_vec();
+            let pixels = vec![0xAB; 4096];
+            let mut file = tiff(
+                &[(0x0100, 4, 1, u32_bytes(64)), (0x0111, 4, 1, u32_bytes(0)), (0x0117, 4, 1, u32_bytes(4096)), ascii(0x010F, "Fastigiata"), short(0x0112, 6, big_endian)],
+                &[ascii(0x9003, "2024:05:01 12:30:00")],
+                &[ascii(0x0001, "N"), rationals(0x0002, &[(48, 1), (51, 1), (24, 1)], big_endian), ascii(0x0003, "E"), rationals(0x0004, &[(2, 1), (21, 1), (7, 1)], big_endian)],
+                big_endian,
+            );
+            file.extend_from_slice(&pixels);
+
+            let exif = Metadata::read(&file, ImageFormat::Tiff).exif.unwrap();
+            assert!(exif.len() < 400, "{}", exif.len());
+            assert!(!exif.windows(16).any(|w| w == &pixels[..16]));
+
+            let tiff = Tiff::new(&exif).unwrap();
+            let ifd0 = tiff.first_ifd().unwrap();
+            assert!(tiff.entry(ifd0, 0x0111).is_none() && tiff.entry(ifd0, 0x0100).is_none());
+            let info = exif_info(&exif).unwrap();
+            assert_eq!(info.make.as_deref(), Some("Fastigiata"));
+            assert_eq!(info.orientation, Some(6));
+            assert_eq!(info.date_time_original.as_deref(), Some("2024:05:01 12:30:00"));
+            assert!((info.gps.unwrap().latitude - 48.856_666).abs() < 1e-5);
+        }
+
+        // a TIFF with only image tags has no EXIF to keep
+        let plain = tiff(&[(0x0100, 3, 1, vec![1, 0])], &[], &[], false);
+        let plain = [&plain[..8], &[1, 0], &plain[10..22], &[0; 4]].concat();
+        assert!(Metadata::read(&plain, ImageFormat::Tiff).exif.is_none());
+    }
+
+    #[test]
+    fn jpeg_too_large() {
+        let jpeg = encoded(image::ImageOutputFormat::Jpeg(80));
+        let all = Keep { exif: true, xmp: true, iptc: true, icc: true, strip_gps: false };
+        let code = |metadata: Metadata| metadata.embed(jpeg.clone(), ImageFormat::Jpeg, all).err().map(|err| err.status);
+
+        let exif = tiff(&[(0x927C, 7, 70000, vec![0; 70000])], &[], &[], false);
+        assert_eq!(code(Metadata { exif: Some(exif), ..Default::default() }), Some(ErrorCode::TooLarge));
+        assert_eq!(code(Metadata { xmp: Some(vec![b' '; 70000]), ..Default::default() }), Some(ErrorCode::TooLarge));
+        assert_eq!(code(Metadata { iptc: Some(vec![0; 70000]), ..Default::default() }), Some(ErrorCode::TooLarge));
+
+        // 255 segments of an ICC profile fit, one more does not
+        let per_segment = u16::MAX as usize - 2 - JPEG_ICC.len() - 2;
+        let icc = |len| Metadata { icc: Some(vec![7; len]), ..Default::default() };
+        let embedded = icc(per_segment * 255).embed(jpeg.clone(), ImageFormat::Jpeg, all).unwrap();
+        assert_eq!(Metadata::read(&embedded, ImageFormat::Jpeg).icc.map(|icc| icc.len()), Some(per_segment * 255));
+        assert_eq!(code(icc(per_segment * 255 + 1)), Some(ErrorCode::TooLarge));
+    }
+}
@@ -0,0 +1,417 @@
+use std::io::Cursor;
+use image::codecs::bmp::BmpDecoder;
+use image::codecs::dds::DdsDecoder;
+use image::codecs::farbfeld::FarbfeldDecoder;
+use image::codecs::gif::GifDecoder;
+use image::codecs::hdr::HdrAdapter;
+use image::codecs::ico::IcoDecoder;
+use image::codecs::jpeg::JpegDecoder;
+use image::codecs::openexr::OpenExrDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::pnm::PnmDecoder;
+use image::codecs::qoi::QoiDecoder;
+use image::codecs::tga::TgaDecoder;
+use image::codecs::tiff::TiffDecoder;
+use image::{ColorType, ImageDecoder, ImageFormat, ImageResult};
+use crate::common::color_type_name;
+use crate::error::{decode_error, error, ErrorCode, Result};
+
+/// Header information of an image, read without decoding the pixel data
+#[napi(object)]
+pub struct ImageInfo {
+    /// The format name, e.g. `png`, `jpeg`, `openexr`
+    pub format: String,
+    /// The MIME type of the format, e.g. `image/png`
+    pub mime_type: String,
+    pub width: u32,
+    pub height: u32,
+    /// The color type the image decodes to, see {@link CommonImage.colorType}
+    #[napi(ts_type = "'l8'|'la8'|'rgb8'|'rgba8'|'l16'|'la16'|'rgb16'|'rgba16'|'rgb32f'|'rgba32f'|'unknown_since_non_exhaustive'")]
+    pub color_type: String,
+    /// Bits per pixel of the decoded image
+    pub bpp: u16,
+    /// The number of frames, `undefined` if the header does not tell it (TIFF and ICO)
+    pub frames: Option<u32>,
+}
+
+/// How the area of a frame is treated before the next frame is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disposal {
+    /// leave the frame in place
+    None,
+    /// clear the area of the frame to the background
+    Background,
+    /// restore the area of the frame to what it was before the frame was rendered
+    Previous,
+}
+
+impl Disposal {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Disposal::None => "none",
+            Disposal::Background => "background",
+            Disposal::Previous => "previous",
+        }
+    }
+}
+
+/// The rectangle and disposal of a frame, as stored in the container of an animated image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameLayout {
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+    pub disposal: Disposal,
+}
+
+/// Get the name of a format, which is the lowercase suffix of the corresponding `ImageLoader.from*` method
+pub fn format_name(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => "png",
+        ImageFormat::Jpeg => "jpeg",
+        ImageFormat::Gif => "gif",
+        ImageFormat::WebP => "webp",
+        ImageFormat::Pnm => "pnm",
+        ImageFormat::Tiff => "tiff",
+        ImageFormat::Tga => "tga",
+        ImageFormat::Dds => "dds",
+        ImageFormat::Bmp => "bmp",
+        ImageFormat::Ico => "ico",
+        ImageFormat::Hdr => "hdr",
+        ImageFormat::OpenExr => "openexr",
+        ImageFormat::Farbfeld => "farbfeld",
+        ImageFormat::Avif => "avif",
+        ImageFormat::Qoi => "qoi",
+        _ => "unknown_since_non_exhaustive",
+    }
+}
+
+/// Parse a format name, file extension or MIME type, e.g. `png`, `.jpg` or `image/webp`
+pub fn parse_format(name: &str) -> Result<ImageFormat> {
+    let lower = name.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    let format = match lower.contains('/') {
+        true => ImageFormat::from_mime_type(&lower),
+        false => {
+            let extension = lower.strip_prefix('.').unwrap_or(&lower);
+            // the names of `format_name` that are no file extension
+            ImageFormat::from_extension(extension).or(match extension {
+                "openexr" => Some(ImageFormat::OpenExr),
+                "pnm" => Some(ImageFormat::Pnm),
+                _ => None,
+            })
+        }
+    };
+    format.ok_or_else(|| error(ErrorCode::Unsupported, "Unsupported format", name))
+}
+
+/// Sniff the format from the magic bytes, TGA has none and is never detected
+pub fn detect_format(buffer: &[u8]) -> Option<ImageFormat> {
+    image::guess_format(buffer).ok()
+}
+
+fn header<'a, D: ImageDecoder<'a>>(decoder: ImageResult<D>) -> Result<(u32, u32, ColorType)> {
+    let decoder = decoder.map_err(decode_error)?;
+    let (w, h) = decoder.dimensions();
+    Ok((w, h, decoder.color_type()))
+}
+
+/// Read the format, dimensions and color type of an image from its header
+pub fn probe(buffer: &[u8]) -> Result<ImageInfo> {
+    let format = image::guess_format(buffer).map_err(decode_error)?;
+    let reader = Cursor::new(buffer);
+
+    let (width, height, color) = match format {
+        ImageFormat::Png => header(PngDecoder::new(reader))?,
+        ImageFormat::Jpeg => header(JpegDecoder::new(reader))?,
+        ImageFormat::Gif => header(GifDecoder::new(reader))?,
+        // the WebP decoder of the underlying library decodes the whole image on creation
+        ImageFormat::WebP => webp_header(buffer)?,
+        ImageFormat::Pnm => header(PnmDecoder::new(reader))?,
+        ImageFormat::Tiff => header(TiffDecoder::new(reader))?,
+        ImageFormat::Tga => header(TgaDecoder::new(reader))?,
+        ImageFormat::Dds => header(DdsDecoder::new(reader))?,
+        ImageFormat::Bmp => header(BmpDecoder::new(reader))?,
+        ImageFormat::Ico => header(IcoDecoder::new(reader))?,
+        ImageFormat::Hdr => header(HdrAdapter::new(reader))?,
+        ImageFormat::OpenExr => header(OpenExrDecoder::new(reader))?,
+        ImageFormat::Farbfeld => header(FarbfeldDecoder::new(reader))?,
+        ImageFormat::Qoi => header(QoiDecoder::new(reader))?,
+        _ => return Err(error(ErrorCode::Unsupported, "Unsupported", format!("probing {} is not supported", format_name(format)))),
+    };
+
+    let frames = match format {
+        ImageFormat::Png => Some(png_frames(buffer).unwrap_or(1)),
+        ImageFormat::Gif => gif_layouts(buffer).map(|layouts| layouts.len() as u32),
+        ImageFormat::WebP => webp_layouts(buffer).map(|layouts| (layouts.len() as u32).max(1)),
+        ImageFormat::Tiff | ImageFormat::Ico => None,
+        _ => Some(1),
+    };
+
+    Ok(ImageInfo {
+        format: format_name(format).to_string(),
+        mime_type: format.to_mime_type().to_string(),
+        width,
+        height,
+        color_type: color_type_name(color).to_string(),
+        bpp: color.bits_per_pixel(),
+        frames,
+    })
+}
+
+fn u16_le(buf: &[u8], at: usize) -> Option<u32> {
+    buf.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as u32)
+}
+
+fn u24_le(buf: &[u8], at: usize) -> Option<u32> {
+    buf.get(at..at + 3).map(|b| u32::from_le_bytes([b[0], b[1], b[2], 0]))
+}
+
+fn u32_le(buf: &[u8], at: usize) -> Option<u32> {
+    buf.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn u32_be(buf: &[u8], at: usize) -> Option<u32> {
+    buf.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Get the number of frames from the `acTL` chunk, `None` if the PNG is not animated
+fn png_frames(buffer: &[u8]) -> Option<u32> {
+    // skip the 8-byte signature, then walk the chunks until the image data starts
+    let mut at = 8;
+    loop {
+        let len = u32_be(buffer, at)? as usize;
+        match buffer.get(at + 4..at + 8)? {
+            b"acTL" => return u32_be(buffer, at + 8),
+            b"IDAT" => return None,
+            _ => at += 12 + len,
+        }
+    }
+}
+
+/// Get the layout of every frame of a GIF by skipping the data sub-blocks without decompressing them
+pub fn gif_layouts(buffer: &[u8]) -> Option<Vec<FrameLayout>> {
+    let skip_sub_blocks = |mut at: usize| -> Option<usize> {
+        loop {
+            let size = *buffer.get(at)? as usize;
+            at += 1 + size;
+            if size == 0 {
+                return Some(at);
+            }
+        }
+    };
+    let color_table_len = |packed: u8| if packed & 0x80 != 0 { 3 << ((packed & 0x07) + 1) } else { 0 };
+
+    // header and logical screen descriptor
+    let mut at = 13 + color_table_len(*buffer.get(10)?);
+    let mut disposal = Disposal::None;
+    let mut layouts = vec![];
+    loop {
+        match *buffer.get(at)? {
+            // extension: introducer, label, sub-blocks
+            0x21 => {
+                // graphic control extension, the disposal method is in bits 2-4 of its packed field
+                if *buffer.get(at + 1)? == 0xF9 {
+                    disposal = match (*buffer.get(at + 3)? >> 2) & 0x07 {
+                        2 => Disposal::Background,
+                        3 => Disposal::Previous,
+                        _ => Disposal::None,
+                    };
+                }
+                at = skip_sub_blocks(at + 2)?;
+            }
+            // image descriptor: separator, 9 bytes, local color table, lzw code size, sub-blocks
+            0x2C => {
+                layouts.push(FrameLayout {
+                    left: u16_le(buffer, at + 1)?,
+                    top: u16_le(buffer, at + 3)?,
+                    width: u16_le(buffer, at + 5)?,
+                    height: u16_le(buffer, at + 7)?,
+                    disposal,
+                });
+                disposal = Disposal::None;
+                at += 10 + color_table_len(*buffer.get(at + 9)?) + 1;
+                at = skip_sub_blocks(at)?;
+            }
+            0x3B => return Some(layouts),
+            _ => return None,
+        }
+    }
+}
+
+/// Get the layout of every frame of an APNG from its `fcTL` chunks
+pub fn png_layouts(buffer: &[u8]) -> Option<Vec<FrameLayout>> {
+    let mut at = 8;
+    let mut layouts = vec![];
+    loop {
+        let len = u32_be(buffer, at)? as usize;
+        match buffer.get(at + 4..at + 8)? {
+            // sequence number, width, height, x offset, y offset, delay (4 bytes), dispose op, blend op
+            b"fcTL" => layouts.push(FrameLayout {
+                left: u32_be(buffer, at + 20)?,
+                top: u32_be(buffer, at + 24)?,
+                width: u32_be(buffer, at + 12)?,
+                height: u32_be(buffer, at + 16)?,
+                disposal: match *buffer.get(at + 8 + 24)? {
+                    1 => Disposal::Background,
+                    2 => Disposal::Previous,
+                    _ => Disposal::None,
+                },
+            }),
+            b"IEND" => return Some(layouts),
+            _ => {}
+        }
+        at += 12 + len;
+    }
+}
+
+/// Walk the RIFF chunks of a WebP, calling `visit` with the fourcc and data of each top-level chunk
+pub fn webp_chunks<'a>(buffer: &'a [u8], mut visit: impl FnMut(&'a [u8], &'a [u8])) -> Option<()> {
+    if buffer.get(0..4)? != b"RIFF" || buffer.get(8..12)? != b"WEBP" {
+        return None;
+    }
+
+    let mut at = 12;
+    while at + 8 <= buffer.len() {
+        let fourcc = &buffer[at..at + 4];
+        let len = u32_le(buffer, at + 4)? as usize;
+        let data = buffer.get(at + 8..(at + 8 + len).min(buffer.len()))?;
+        visit(fourcc, data);
+        // chunks are padded to an even size
+        at += 8 + len + (len & 1);
+    }
+    Some(())
+}
+
+/// Append a RIFF chunk, padded to an even length
+pub fn riff_chunk(buf: &mut Vec<u8>, fourcc: &[u8; 4], payload: &[u8]) {
+    buf.extend_from_slice(fourcc);
+    buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buf.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        buf.push(0);
+    }
+}
+
+/// Read the dimensions and color type from the header of a WebP
+pub fn webp_header(buffer: &[u8]) -> Result<(u32, u32, ColorType)> {
+    let invalid = || error(ErrorCode::Decode, "Decode failed", "invalid WebP header");
+
+    let mut first: Option<(&[u8], &[u8])> = None;
+    let mut lossless = false;
+    webp_chunks(buffer, |fourcc, data| {
+        if first.is_none() {
+            first = Some((fourcc, data));
+        }
+        // the lossless bitstream may be at the top-level or inside the first animation frame
+        lossless |= fourcc == b"VP8L" || (fourcc == b"ANMF" && data.get(16..20) == Some(b"VP8L"));
+    }).ok_or_else(invalid)?;
+
+    match first.ok_or_else(invalid)? {
+        // frame tag (3 bytes), start code (3 bytes), then 14-bit width and height
+        (b"VP8 ", data) => match (u16_le(data, 6), u16_le(data, 8)) {
+            (Some(w), Some(h)) => Ok((w & 0x3FFF, h & 0x3FFF, ColorType::Rgb8)),
+            _ => Err(invalid()),
+        },
+        // signature (1 byte), then 14-bit width - 1 and height - 1
+        (b"VP8L", data) => match u32_le(data, 1) {
+            Some(bits) => Ok(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1, ColorType::Rgba8)),
+            None => Err(invalid()),
+        },
+        // flags (1 byte), reserved (3 bytes), then 24-bit canvas width - 1 and height - 1
+        (b"VP8X", data) => match (data.first(), u24_le(data, 4), u24_le(data, 7)) {
+            (Some(flags), Some(w), Some(h)) => {
+                let alpha = flags & 0x10 != 0 || lossless;
+                Ok((w + 1, h + 1, if alpha { ColorType::Rgba8 } else { ColorType::Rgb8 }))
+            }
+            _ => Err(invalid()),
+        },
+        _ => Err(invalid()),
+    }
+}
+
+/// Get the layout of every frame of an animated WebP from its `ANMF` chunks
+pub fn webp_layouts(buffer: &[u8]) -> Option<Vec<FrameLayout>> {
+    let mut layouts = vec![];
+    let mut valid = true;
+    webp_chunks(buffer, |fourcc, data| {
+        if fourcc != b"ANMF" {
+            return;
+        }
+        // x / 2, y / 2, width - 1, height - 1 (24 bits each), duration (24 bits), flags
+        match (u24_le(data, 0), u24_le(data, 3), u24_le(data, 6), u24_le(data, 9), data.get(15)) {
+            (Some(x), Some(y), Some(w), Some(h), Some(flags)) => layouts.push(FrameLayout {
+                left: x * 2,
+                top: y * 2,
+                width: w + 1,
+                height: h + 1,
+                disposal: if flags & 0x01 != 0 { Disposal::Background } else { Disposal::None },
+            }),
+            _ => valid = false,
+        }
+    })?;
+    valid.then_some(layouts)
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+
+    fn source(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/__test__/_source/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    #[test]
+    fn parse_formats() {
+        assert_eq!(parse_format("png").unwrap(), ImageFormat::Png);
+        assert_eq!(parse_format(".JPG").unwrap(), ImageFormat::Jpeg);
+        assert_eq!(parse_format("image/webp").unwrap(), ImageFormat::WebP);
+        assert_eq!(parse_format("image/png; charset=binary").unwrap(), ImageFormat::Png);
+        assert_eq!(parse_format("openexr").unwrap(), ImageFormat::OpenExr);
+        assert_eq!(parse_format("image/x-portable-graymap").unwrap(), ImageFormat::Pnm);
+        assert!(parse_format("image/heic").is_err());
+        assert!(parse_format("txt").is_err());
+
+        assert_eq!(detect_format(b"\x89PNG\r\n\x1a\n"), Some(ImageFormat::Png));
+        assert_eq!(detect_format(b"not an image"), None);
+    }
+
+    #[test]
+    fn probe_matches_decode() {
+        for name in ["tree.png", "tree.jpeg", "tree.gif", "tree.webp", "tree.pnm", "tree.tiff", "tree.bmp", "tree.ico", "tree.hdr", "tree.exr", "tree.ff", "tree.qoi"] {
+            let buffer = source(name);
+            let info = probe(&buffer).unwrap();
+            let img = image::load_from_memory(&buffer).unwrap();
+
+            assert_eq!((info.width, info.height), (img.width(), img.height()), "{}", name);
+            assert_eq!(info.color_type, color_type_name(img.color()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn probe_format() {
+        let info = probe(&source("tree.jpeg")).unwrap();
+        assert_eq!(info.format, "jpeg");
+        assert_eq!(info.mime_type, "image/jpeg");
+        assert_eq!(info.frames, Some(1));
+        assert_eq!(probe(&source("tree.gif")).unwrap().frames, Some(1));
+    }
+
+    #[test]
+    fn probe_animated_gif() {
+        let mut buffer = vec![];
+        {
+            let mut encoder = image::codecs::gif::GifEncoder::new(&mut buffer);
+            let frames = (0..3).map(|_| image::Frame::new(image::RgbaImage::new(4, 4)));
+            encoder.encode_frames(frames).unwrap();
+        }
+        assert_eq!(probe(&buffer).unwrap().frames, Some(3));
+    }
+
+    #[test]
+    fn probe_truncated() {
+        let buffer = source("tree.png");
+        assert_eq!(probe(&buffer[..16]).err().unwrap().status, ErrorCode::Decode);
+        assert_eq!(probe(b"not an image").err().unwrap().status, ErrorCode::Unsupported);
+    }
+}
@@ -0,0 +1,40 @@
+use napi::bindgen_prelude::{AsyncTask, JsError, ToNapiValue, TypeName};
+use napi::{Env, Error, Task};
+use crate::error::{error, ErrorCode, Result};
+
+type Job<T> = Box<dyn FnOnce() -> Result<T> + Send>;
+
+/// A one-shot job that runs on the libuv thread pool and resolves to `T` on the main thread
+pub struct ImageTask<T> {
+    job: Option<Job<T>>,
+}
+
+impl<T> ImageTask<T>
+    where T: ToNapiValue + TypeName + Send + 'static
+{
+    /// Wrap a job into an `AsyncTask`, so that it can be returned to JavaScript as a Promise
+    pub fn spawn<F>(job: F) -> AsyncTask<Self>
+        where F: FnOnce() -> Result<T> + Send + 'static
+    {
+        AsyncTask::new(Self { job: Some(Box::new(job)) })
+    }
+}
+
+impl<T> Task for ImageTask<T>
+    where T: ToNapiValue + TypeName + Send + 'static
+{
+    // the job error is carried to `resolve`, so that the rejected `Error` keeps its `code`
+    type Output = Result<T>;
+    type JsValue = T;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        match self.job.take() {
+            Some(job) => Ok(job()),
+            None => Ok(Err(error(ErrorCode::Internal, "Task already consumed", "This should never happen, please report this issue to me!"))),
+        }
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        output.map_err(|err| Error::from(JsError::from(err).into_unknown(env)))
+    }
+}